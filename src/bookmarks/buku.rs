@@ -0,0 +1,164 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{Context, Result};
+use jiff::Timestamp;
+use rusqlite::{Connection, OpenFlags};
+
+use crate::bookmarks::model::{folder_by_path, split_tags, BookmarkMeta, BookmarkNode};
+
+/// A row of buku's `bookmarks` table
+struct BukuRow {
+    id: i64,
+    url: String,
+    /// The title
+    metadata: String,
+    /// Comma-separated and comma-wrapped, e.g. `,rust,docs,`
+    tags: String,
+    desc: String,
+    /// Bit 0 marks the title as immutable
+    flags: i64,
+}
+
+/// Reads the bookmarks out of a buku `bookmarks.db` as a flat list of links, or into folders
+/// named after each bookmark's first tag
+///
+/// buku doesn't record when bookmarks were added, so every bookmark is dated at the database's
+/// modification time.
+pub fn bookmarks_from_buku(path: &Path, first_tag_folders: bool) -> Result<Vec<BookmarkNode>> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let rows = read_rows(&connection)
+        .with_context(|| format!("Failed to read bookmarks from {}", path.display()))?;
+    let date = Timestamp::try_from(fs::metadata(path)?.modified()?)?;
+
+    let mut children = vec![];
+    for row in rows {
+        let tags = split_tags(&row.tags, |c| c == ',');
+        let folder = match tags.as_ref().and_then(|tags| tags.first()) {
+            Some(tag) if first_tag_folders => folder_by_path(&mut children, &[tag.as_str()]),
+            _ => &mut children,
+        };
+        let annotations = match row.flags {
+            0 => BTreeMap::new(),
+            flags => BTreeMap::from([("flags".to_string(), flags.to_string())]),
+        };
+        folder.push(BookmarkNode::Link {
+            title: row.metadata,
+            url: row.url,
+            add_date: date,
+            last_modified: date,
+            tags,
+            meta: BookmarkMeta {
+                id: Some(row.id.to_string()),
+                index: Some(folder.len() as u32),
+                description: Some(row.desc).filter(|desc| !desc.is_empty()),
+                annotations,
+                ..Default::default()
+            },
+        });
+    }
+
+    Ok(children)
+}
+
+fn read_rows(connection: &Connection) -> Result<Vec<BukuRow>> {
+    let mut statement = connection
+        .prepare("SELECT id, URL, metadata, tags, desc, flags FROM bookmarks ORDER BY id")?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok(BukuRow {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                metadata: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                tags: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                desc: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                flags: row.get::<_, Option<i64>>(5)?.unwrap_or_default(),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmarks::BookmarkFormat;
+    use pretty_assertions::assert_eq;
+
+    /// Builds a `bookmarks.db` with the schema buku creates
+    fn create_fixture(path: &Path) {
+        let connection = Connection::open(path).unwrap();
+        connection
+            .execute_batch(
+                r#"
+CREATE TABLE if not exists bookmarks (
+    id integer PRIMARY KEY, URL text NOT NULL UNIQUE, metadata text default '',
+    tags text default ',', desc text default '', flags integer default 0
+);
+INSERT INTO bookmarks VALUES
+    (1, 'https://test.website/', 'Test', ',test,test1,', 'A test website', 0),
+    (2, 'https://untagged.website/', 'Untagged', ',', '', 1),
+    (3, 'https://other.website/', 'Other', ',test,', '', 0);
+"#,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn buku_works() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        create_fixture(&path);
+        assert_eq!(
+            Some(BookmarkFormat::Buku),
+            BookmarkFormat::detect(&fs::read(&path).unwrap())
+        );
+
+        let date = Timestamp::try_from(fs::metadata(&path).unwrap().modified().unwrap()).unwrap();
+        let link = |id: &str, title: &str, index, tags: &[&str]| BookmarkNode::Link {
+            title: title.to_string(),
+            url: format!("https://{}.website/", title.to_lowercase()),
+            add_date: date,
+            last_modified: date,
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            meta: BookmarkMeta {
+                id: Some(id.to_string()),
+                index: Some(index),
+                ..Default::default()
+            },
+        };
+        let mut test = link("1", "Test", 0, &["test", "test1"]);
+        let mut untagged = link("2", "Untagged", 1, &[]);
+        if let BookmarkNode::Link { meta, .. } = &mut test {
+            meta.description = Some("A test website".to_string());
+        }
+        if let BookmarkNode::Link { tags, meta, .. } = &mut untagged {
+            *tags = None;
+            meta.annotations = BTreeMap::from([("flags".to_string(), "1".to_string())]);
+        }
+
+        assert_eq!(
+            vec![
+                test.clone(),
+                untagged.clone(),
+                link("3", "Other", 2, &["test"])
+            ],
+            bookmarks_from_buku(&path, false).unwrap()
+        );
+
+        let expected_folder = BookmarkNode::Folder {
+            title: "test".to_string(),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            children: vec![test, link("3", "Other", 1, &["test"])],
+            meta: BookmarkMeta {
+                index: Some(0),
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            vec![expected_folder, untagged],
+            bookmarks_from_buku(&path, true).unwrap()
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use url::Url;
+
+/// Query parameters that only record where a visitor came from (`utm_*` is matched separately)
+const TRACKING_PARAMS: &[&str] = &[
+    "_ga",
+    "_gl",
+    "_hsenc",
+    "_hsmi",
+    "dclid",
+    "fbclid",
+    "gbraid",
+    "gclid",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "mkt_tok",
+    "msclkid",
+    "oly_anon_id",
+    "oly_enc_id",
+    "rb_clickid",
+    "ref_src",
+    "s_cid",
+    "twclid",
+    "vero_id",
+    "wbraid",
+    "wickedid",
+    "yclid",
+];
+
+/// The parts of a URL canonicalization should leave alone; by default every step is applied
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CanonicalOptions {
+    pub keep_fragment: bool,
+    pub keep_tracking: bool,
+    pub keep_query_order: bool,
+    pub keep_trailing_slash: bool,
+}
+
+/// Puts a URL in a canonical form, so copies of a link saved in slightly different ways compare
+/// equal
+///
+/// Parsing lowercases the scheme and host, converts internationalized domain names to punycode and
+/// drops default ports. Unless `opts` says otherwise, tracking parameters are then removed, the
+/// query is sorted by key, and the fragment and any trailing slash on the path are dropped. URLs
+/// that don't parse are only trimmed.
+pub fn canonicalize_url(url: &str, opts: &CanonicalOptions) -> String {
+    let url = url.trim();
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+
+    if !opts.keep_fragment {
+        parsed.set_fragment(None);
+    }
+    if let Some(query) = parsed
+        .query()
+        .filter(|_| !(opts.keep_tracking && opts.keep_query_order))
+    {
+        // The pairs are kept exactly as written, since re-encoding them would turn `?flag` into
+        // `?flag=` and `%20` into `+`
+        let mut pairs = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| opts.keep_tracking || !is_tracking_param(param_key(pair)))
+            .collect::<Vec<_>>();
+        if !opts.keep_query_order {
+            // Stable, so repeated keys keep their order
+            pairs.sort_by_key(|pair| param_key(pair));
+        }
+        let query = pairs.join("&");
+        parsed.set_query(Some(query.as_str()).filter(|query| !query.is_empty()));
+    }
+    if !opts.keep_trailing_slash && !parsed.cannot_be_a_base() {
+        let path = parsed.path();
+        // The root path is always there, so only deeper ones lose their slash
+        if path.len() > 1 && path.ends_with('/') {
+            let path = path.trim_end_matches('/').to_string();
+            parsed.set_path(&path);
+        }
+    }
+    parsed.to_string()
+}
+
+/// The canonical form a link is recognised by from one run to the next, which always uses the
+/// default options so that changing them doesn't make every link look new
+pub fn stable_url(url: &str) -> String {
+    canonicalize_url(url, &CanonicalOptions::default())
+}
+
+/// The (still encoded) key of a `key=value` query pair
+fn param_key(pair: &str) -> &str {
+    pair.split_once('=').map_or(pair, |(key, _)| key)
+}
+
+fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn canonicalize_url_works() {
+        let canonical = |url| canonicalize_url(url, &CanonicalOptions::default());
+        assert_eq!(
+            "https://test.website/",
+            canonical("  HTTPS://Test.Website:443#top ")
+        );
+        assert_eq!(
+            "http://xn--bcher-kva.example/shelf",
+            canonical("http://Bücher.example:80/shelf/")
+        );
+        assert_eq!(
+            "https://test.website/search?a=2&q=rust&q=book",
+            canonical("https://test.website/search?utm_source=feed&q=rust&a=2&fbclid=x&q=book")
+        );
+        assert_eq!(
+            "https://test.website/",
+            canonical("https://test.website/?utm_medium=email&GCLID=1")
+        );
+        assert_eq!(
+            "https://test.website:8443/",
+            canonical("https://test.website:8443")
+        );
+        assert_eq!(
+            "mailto:Someone@Example.com",
+            canonical("MAILTO:Someone@Example.com")
+        );
+        assert_eq!(
+            "https://test.website/search?flag&q=two%20words+more",
+            canonical("https://test.website/search?q=two%20words+more&utm_campaign=x&flag")
+        );
+        assert_eq!("not a url", canonical("not a url"));
+    }
+
+    #[test]
+    fn canonicalize_url_steps_can_be_skipped() {
+        let url = "https://test.website/docs/?b=1&utm_source=feed&a=2#intro";
+        let opts = CanonicalOptions {
+            keep_fragment: true,
+            keep_tracking: true,
+            keep_query_order: true,
+            keep_trailing_slash: true,
+        };
+        assert_eq!(url, canonicalize_url(url, &opts));
+        let opts = CanonicalOptions {
+            keep_tracking: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            "https://test.website/docs?a=2&b=1&utm_source=feed",
+            canonicalize_url(url, &opts)
+        );
+    }
+}
@@ -1,39 +1,129 @@
+use std::collections::BTreeMap;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// Timestamps are microseconds since 1601-01-01 stored as strings, with "0" meaning never
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum ChromeBookmarkNode {
     Folder {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        guid: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
         name: String,
         date_added: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        date_last_used: Option<String>,
+        #[serde(default)]
         date_modified: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        meta_info: Option<BTreeMap<String, String>>,
         children: Vec<ChromeBookmarkNode>,
     },
     Url {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        guid: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
         name: String,
         date_added: String,
-        date_last_used: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        date_last_used: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        meta_info: Option<BTreeMap<String, String>>,
         url: String,
     },
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// The built-in folders, any of which may be missing
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ChromeBookmarkRoots {
-    pub bookmark_bar: ChromeBookmarkNode,
-    pub other: ChromeBookmarkNode,
-    pub synced: ChromeBookmarkNode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bookmark_bar: Option<ChromeBookmarkNode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other: Option<ChromeBookmarkNode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced: Option<ChromeBookmarkNode>,
+    /// Roots added by other Chrom(e|ium) versions or forks, kept as-is
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ChromeBookmarks {
     pub checksum: String,
     pub roots: ChromeBookmarkRoots,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_metadata: Option<String>,
     pub version: u8,
 }
 
+impl ChromeBookmarks {
+    /// Computes the checksum Chrom(e|ium) stores alongside the roots
+    ///
+    /// It's the hex MD5 of every node's id, UTF-16LE name and type ("url" or "folder", followed by
+    /// the URL for urls), visited depth-first through the bookmark bar, other and synced roots.
+    /// Unknown roots aren't part of it.
+    pub fn compute_checksum(&self) -> String {
+        fn update(context: &mut md5::Context, node: &ChromeBookmarkNode) {
+            let (ChromeBookmarkNode::Folder { id, name, .. }
+            | ChromeBookmarkNode::Url { id, name, .. }) = node;
+            context.consume(id.as_deref().unwrap_or_default());
+            for unit in name.encode_utf16() {
+                context.consume(unit.to_le_bytes());
+            }
+            match node {
+                ChromeBookmarkNode::Folder { children, .. } => {
+                    context.consume("folder");
+                    for child in children {
+                        update(context, child);
+                    }
+                }
+                ChromeBookmarkNode::Url { url, .. } => {
+                    context.consume("url");
+                    context.consume(url);
+                }
+            }
+        }
+
+        let mut context = md5::Context::new();
+        let roots = [
+            &self.roots.bookmark_bar,
+            &self.roots.other,
+            &self.roots.synced,
+        ];
+        for root in roots.into_iter().flatten() {
+            update(&mut context, root);
+        }
+        format!("{:x}", context.finalize())
+    }
+
+    /// Whether the stored checksum matches the roots, which fails when the file was read while
+    /// Chrom(e|ium) was halfway through saving it
+    pub fn checksum_matches(&self) -> bool {
+        self.checksum == self.compute_checksum()
+    }
+}
+
+/// Attempts to deserialize JSON data representing Chrom(e|ium) bookmarks
 pub fn bookmarks_from_chrome_json(data: &str) -> Result<ChromeBookmarks> {
-    todo!()
+    let result: ChromeBookmarks = serde_json::from_str(data)?;
+    Ok(result)
+}
+
+/// Serializes Chrom(e|ium) bookmarks with a freshly computed checksum, since Chrom(e|ium) doesn't
+/// trust a `Bookmarks` file whose checksum doesn't match
+// Nothing writes Chrome files yet
+#[allow(dead_code)]
+pub fn bookmarks_to_chrome_json(bookmarks: &ChromeBookmarks) -> Result<String> {
+    let bookmarks = ChromeBookmarks {
+        checksum: bookmarks.compute_checksum(),
+        ..bookmarks.clone()
+    };
+    Ok(serde_json::to_string_pretty(&bookmarks)?)
 }
 
 #[cfg(test)]
@@ -48,38 +138,193 @@ mod tests {
         let expected = ChromeBookmarks {
             checksum: "091d7dab6aa5b695a642ef96765ab1ed".to_string(),
             roots: ChromeBookmarkRoots {
-                bookmark_bar: ChromeBookmarkNode::Folder {
+                bookmark_bar: Some(ChromeBookmarkNode::Folder {
+                    guid: Some("0bc5d13f-2cba-5d74-951f-3f233fe6c908".to_string()),
+                    id: Some("1".to_string()),
                     name: "Bookmarks bar".to_string(),
-                    date_modified: "0".to_string(),
                     date_added: "13385507687119717".to_string(),
+                    date_last_used: Some("0".to_string()),
+                    date_modified: "0".to_string(),
+                    meta_info: None,
                     children: vec![],
-                },
-                other: ChromeBookmarkNode::Folder {
+                }),
+                other: Some(ChromeBookmarkNode::Folder {
+                    guid: Some("82b081ec-3dd3-529c-8475-ab6c344590dd".to_string()),
+                    id: Some("2".to_string()),
                     name: "Other bookmarks".to_string(),
                     date_added: "13385507687119721".to_string(),
+                    date_last_used: Some("0".to_string()),
                     date_modified: "13385507767527863".to_string(),
+                    meta_info: None,
                     children: vec![ChromeBookmarkNode::Folder {
+                        guid: Some("96fee9ac-4bec-49a8-839c-6e101708a9d5".to_string()),
+                        id: Some("5".to_string()),
                         name: "Test".to_string(),
                         date_added: "13385507709128841".to_string(),
+                        date_last_used: Some("0".to_string()),
                         date_modified: "13385507771273144".to_string(),
+                        meta_info: None,
                         children: vec![ChromeBookmarkNode::Url {
+                            guid: Some("5c91cdef-0664-4346-9dfc-85dd93e5b751".to_string()),
+                            id: Some("6".to_string()),
                             name: "test.website".to_string(),
                             date_added: "13385507767527863".to_string(),
-                            date_last_used: "0".to_string(),
+                            date_last_used: Some("0".to_string()),
+                            meta_info: Some(BTreeMap::from([(
+                                "power_bookmark_meta".to_string(),
+                                "".to_string(),
+                            )])),
                             url: "http://test.website/".to_string(),
                         }],
                     }],
-                },
-                synced: ChromeBookmarkNode::Folder {
+                }),
+                synced: Some(ChromeBookmarkNode::Folder {
+                    guid: Some("4cf2e351-0e85-532b-bb37-df045d8f8d0f".to_string()),
+                    id: Some("3".to_string()),
                     name: "Mobile bookmarks".to_string(),
                     date_added: "13385507687119723".to_string(),
+                    date_last_used: Some("0".to_string()),
                     date_modified: "0".to_string(),
+                    meta_info: None,
                     children: vec![],
-                },
+                }),
+                extra: BTreeMap::new(),
             },
+            sync_metadata: None,
             version: 1,
         };
         let result: ChromeBookmarks = serde_json::from_str(input).unwrap();
         assert_eq!(expected, result)
     }
+
+    #[test]
+    fn deserialize_chrome_bookmarks_with_unusual_roots() {
+        let input = r#"{
+            "checksum": "",
+            "roots": {
+                "bookmark_bar": {
+                    "children": [],
+                    "date_added": "0",
+                    "date_modified": "0",
+                    "name": "Bookmarks bar",
+                    "type": "folder"
+                },
+                "workspaces": {
+                    "children": [],
+                    "date_added": "0",
+                    "date_modified": "0",
+                    "name": "Workspaces",
+                    "type": "folder"
+                },
+                "sync_transaction_version": "4"
+            },
+            "version": 1
+        }"#;
+        let result = bookmarks_from_chrome_json(input).unwrap();
+        assert!(result.roots.bookmark_bar.is_some());
+        assert_eq!(None, result.roots.other);
+        assert_eq!(None, result.roots.synced);
+        assert_eq!(
+            vec!["sync_transaction_version", "workspaces"],
+            result.roots.extra.keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn compute_checksum_follows_chromes_algorithm() {
+        let bookmarks = bookmarks_from_chrome_json(
+            r#"{
+                "checksum": "",
+                "roots": {
+                    "bookmark_bar": {
+                        "children": [{
+                            "date_added": "0",
+                            "id": "4",
+                            "name": "Café",
+                            "type": "url",
+                            "url": "http://cafe.website/"
+                        }],
+                        "date_added": "0",
+                        "id": "1",
+                        "name": "Bar",
+                        "type": "folder"
+                    },
+                    "other": {
+                        "children": [],
+                        "date_added": "0",
+                        "id": "2",
+                        "name": "Other",
+                        "type": "folder"
+                    },
+                    "synced": {
+                        "children": [],
+                        "date_added": "0",
+                        "id": "3",
+                        "name": "Mobile",
+                        "type": "folder"
+                    },
+                    "workspaces": {
+                        "children": [],
+                        "date_added": "0",
+                        "id": "5",
+                        "name": "Workspaces",
+                        "type": "folder"
+                    }
+                },
+                "version": 1
+            }"#,
+        )
+        .unwrap();
+        // The bytes Chrom(e|ium)'s BookmarkCodec feeds into its MD5, spelled out by hand
+        let utf16 =
+            |name: &str| -> Vec<u8> { name.encode_utf16().flat_map(u16::to_le_bytes).collect() };
+        let bytes = [
+            b"1".to_vec(),
+            utf16("Bar"),
+            b"folder".to_vec(),
+            b"4".to_vec(),
+            utf16("Café"),
+            b"url".to_vec(),
+            b"http://cafe.website/".to_vec(),
+            b"2".to_vec(),
+            utf16("Other"),
+            b"folder".to_vec(),
+            b"3".to_vec(),
+            utf16("Mobile"),
+            b"folder".to_vec(),
+        ]
+        .concat();
+        assert_eq!(
+            format!("{:x}", md5::compute(bytes)),
+            bookmarks.compute_checksum()
+        );
+    }
+
+    #[test]
+    fn checksum_mismatches_are_detected() {
+        // The fixture's stored checksum was edited by hand and is one digit off its contents
+        let mut bookmarks =
+            bookmarks_from_chrome_json(include_str!("../../example-chromium.json")).unwrap();
+        assert!(!bookmarks.checksum_matches());
+        assert_eq!(
+            "090d7dab6aa5b695a642ef96765ab1ed",
+            bookmarks.compute_checksum()
+        );
+
+        let written =
+            bookmarks_from_chrome_json(&bookmarks_to_chrome_json(&bookmarks).unwrap()).unwrap();
+        assert!(written.checksum_matches());
+        assert_eq!(bookmarks.roots, written.roots);
+
+        let Some(ChromeBookmarkNode::Folder { name, .. }) = &mut bookmarks.roots.bookmark_bar
+        else {
+            panic!("Expected the bookmark bar to be a folder");
+        };
+        *name = "Lesezeichenleiste".to_string();
+        let renamed = ChromeBookmarks {
+            checksum: written.checksum,
+            ..bookmarks
+        };
+        assert!(!renamed.checksum_matches());
+    }
 }
@@ -0,0 +1,458 @@
+use std::{collections::HashMap, fmt};
+
+use clap::ValueEnum;
+use jiff::Timestamp;
+use url::Url;
+
+use crate::bookmarks::{
+    model::{BookmarkNode, Bookmarks},
+    traversal::Visit,
+};
+
+/// What to do with links saved more than once under the same canonical URL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DuplicateStrategy {
+    /// Keep the copy that comes first, with the sources of every copy and the earliest add date
+    First,
+    /// Keep the most recently added copy, with the sources of every copy and the earliest add date
+    Newest,
+    /// Keep the first copy, with the tags and sources of every copy, the earliest add date and a
+    /// list of the folders each copy was in
+    #[default]
+    Merge,
+}
+
+/// Why links were grouped together
+#[derive(Debug, Clone, PartialEq)]
+pub enum DuplicateReason {
+    SameUrl(String),
+    SimilarTitle { domain: String, title: String },
+}
+
+/// Links that look like the same bookmark, in the order they appear
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup<'a> {
+    pub reason: DuplicateReason,
+    pub links: Vec<Visit<'a>>,
+}
+
+impl fmt::Display for DuplicateGroup<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            DuplicateReason::SameUrl(url) => write!(f, "{} copies of {url}", self.links.len())?,
+            DuplicateReason::SimilarTitle { domain, title } => write!(
+                f,
+                "{} similar titles on {domain}: \"{title}\"",
+                self.links.len()
+            )?,
+        }
+        for visit in &self.links {
+            let BookmarkNode::Link {
+                title,
+                url,
+                add_date,
+                ..
+            } = visit.node
+            else {
+                continue;
+            };
+            write!(
+                f,
+                "\n  /{}  \"{title}\"  added {}  {url}",
+                visit.path.join("/"),
+                add_date.strftime("%Y-%m-%d"),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds links saved more than once, first by canonical URL and then by titles that only differ
+/// in case, spacing or punctuation on the same domain
+pub fn find_duplicates(bookmarks: &Bookmarks) -> Vec<DuplicateGroup<'_>> {
+    let mut by_url = Groups::default();
+    let mut by_title = Groups::default();
+    for visit in bookmarks.links() {
+        let BookmarkNode::Link {
+            title, url, meta, ..
+        } = visit.node
+        else {
+            continue;
+        };
+        let key = meta.canonical_url_of(url);
+        let title = simplify_title(title);
+        if let (Some(domain), false) = (domain(&key), title.is_empty()) {
+            by_title.add((domain, title), visit.clone());
+        }
+        by_url.add(key, visit);
+    }
+
+    let same_url = by_url.into_groups().map(|(url, links)| DuplicateGroup {
+        reason: DuplicateReason::SameUrl(url),
+        links,
+    });
+    // Links with the same URL are already reported above
+    let similar_titles = by_title
+        .into_groups()
+        .filter(|(_, links)| {
+            let first = visit_key(&links[0]);
+            links.iter().any(|visit| visit_key(visit) != first)
+        })
+        .map(|((domain, title), links)| DuplicateGroup {
+            reason: DuplicateReason::SimilarTitle { domain, title },
+            links,
+        });
+    same_url.chain(similar_titles).collect()
+}
+
+/// Collapses every set of links with the same canonical URL into one, and returns how many links
+/// were removed
+pub fn dedupe(bookmarks: &mut Bookmarks, strategy: DuplicateStrategy) -> usize {
+    let mut merged: HashMap<String, Merged> = HashMap::new();
+    for visit in bookmarks.links() {
+        let BookmarkNode::Link {
+            add_date,
+            tags,
+            meta,
+            ..
+        } = visit.node
+        else {
+            continue;
+        };
+        let folder = format!("/{}", visit.path.join("/"));
+        let key = visit_key(&visit);
+        let Some(group) = merged.get_mut(&key) else {
+            merged.insert(
+                key,
+                Merged {
+                    keep: 0,
+                    newest: *add_date,
+                    count: 1,
+                    add_date: *add_date,
+                    tags: tags.clone(),
+                    sources: meta.sources.clone(),
+                    folders: vec![folder],
+                },
+            );
+            continue;
+        };
+        if strategy == DuplicateStrategy::Newest && *add_date > group.newest {
+            group.keep = group.count;
+            group.newest = *add_date;
+        }
+        group.count += 1;
+        group.add_date = group.add_date.min(*add_date);
+        for tag in tags.iter().flatten() {
+            let group_tags = group.tags.get_or_insert_with(Vec::new);
+            if !group_tags.contains(tag) {
+                group_tags.push(tag.clone());
+            }
+        }
+        for source in &meta.sources {
+            if !group.sources.contains(source) {
+                group.sources.push(source.clone());
+            }
+        }
+        if !group.folders.contains(&folder) {
+            group.folders.push(folder);
+        }
+    }
+
+    let before = bookmarks.link_count();
+    retain_kept(&mut bookmarks.roots, &merged, &mut HashMap::new(), strategy);
+    before - bookmarks.link_count()
+}
+
+/// Everything known about one set of duplicates
+struct Merged {
+    /// Which copy, in depth-first order, is kept
+    keep: usize,
+    newest: Timestamp,
+    count: usize,
+    add_date: Timestamp,
+    tags: Option<Vec<String>>,
+    sources: Vec<String>,
+    folders: Vec<String>,
+}
+
+/// Drops every copy but the kept one, visiting links in the same order as [`Bookmarks::links`]
+fn retain_kept(
+    nodes: &mut Vec<BookmarkNode>,
+    merged: &HashMap<String, Merged>,
+    seen: &mut HashMap<String, usize>,
+    strategy: DuplicateStrategy,
+) {
+    nodes.retain_mut(|node| match node {
+        BookmarkNode::Link {
+            url,
+            add_date,
+            tags,
+            meta,
+            ..
+        } => {
+            let key = meta.canonical_url_of(url);
+            let group = &merged[&key];
+            let copy = seen.entry(key).or_default();
+            *copy += 1;
+            if *copy - 1 != group.keep {
+                return false;
+            }
+            // Whichever copy is kept, the note says everywhere the link was saved and since when
+            if group.count > 1 {
+                *add_date = group.add_date;
+                meta.sources.clone_from(&group.sources);
+            }
+            if strategy == DuplicateStrategy::Merge && group.count > 1 {
+                tags.clone_from(&group.tags);
+                meta.folders.clone_from(&group.folders);
+            }
+            true
+        }
+        BookmarkNode::Folder { children, .. } => {
+            retain_kept(children, merged, seen, strategy);
+            true
+        }
+        BookmarkNode::Separator { .. } => true,
+    });
+}
+
+fn visit_key(visit: &Visit) -> String {
+    match visit.node {
+        BookmarkNode::Link { url, meta, .. } => meta.canonical_url_of(url),
+        _ => String::new(),
+    }
+}
+
+/// The host of a URL, without any `www.`
+fn domain(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    Some(host.strip_prefix("www.").unwrap_or(host).to_string())
+}
+
+/// Lowercases a title and reduces it to its words
+fn simplify_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Values grouped by key, remembering the order keys were first seen in
+struct Groups<K, V> {
+    keys: Vec<K>,
+    values: HashMap<K, Vec<V>>,
+}
+
+impl<K, V> Default for Groups<K, V> {
+    fn default() -> Self {
+        Self {
+            keys: vec![],
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + std::hash::Hash, V> Groups<K, V> {
+    fn add(&mut self, key: K, value: V) {
+        if !self.values.contains_key(&key) {
+            self.keys.push(key.clone());
+        }
+        self.values.entry(key).or_default().push(value);
+    }
+
+    /// The groups with more than one value, in the order their keys were first seen
+    fn into_groups(mut self) -> impl Iterator<Item = (K, Vec<V>)> {
+        self.keys.into_iter().filter_map(move |key| {
+            let values = self.values.remove(&key)?;
+            (values.len() > 1).then_some((key, values))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmarks::model::{merge_trees, BookmarkMeta, BookmarkSource};
+    use pretty_assertions::assert_eq;
+
+    fn link(title: &str, url: &str, added: &str, tags: &[&str], source: &str) -> BookmarkNode {
+        let add_date = added.parse().unwrap();
+        BookmarkNode::Link {
+            title: title.to_string(),
+            url: url.to_string(),
+            add_date,
+            last_modified: add_date,
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            meta: BookmarkMeta {
+                sources: vec![source.to_string()],
+                ..Default::default()
+            },
+        }
+    }
+
+    fn folder(title: &str, children: Vec<BookmarkNode>) -> BookmarkNode {
+        BookmarkNode::Folder {
+            title: title.to_string(),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            children,
+            meta: BookmarkMeta::default(),
+        }
+    }
+
+    fn sample() -> Bookmarks {
+        Bookmarks::new(
+            vec![
+                folder(
+                    "Dev",
+                    vec![
+                        link(
+                            "Test",
+                            "https://test.website/",
+                            "2025-03-01T00:00:00Z",
+                            &["test"],
+                            "work",
+                        ),
+                        link(
+                            "Rust Docs",
+                            "https://www.docs.website/rust",
+                            "2025-03-01T00:00:00Z",
+                            &[],
+                            "work",
+                        ),
+                    ],
+                ),
+                folder(
+                    "Reading",
+                    vec![link(
+                        "Test website",
+                        "HTTPS://Test.Website/?utm_source=feed#top",
+                        "2025-04-01T00:00:00Z",
+                        &["test", "later"],
+                        "home",
+                    )],
+                ),
+                link(
+                    "rust docs!",
+                    "https://docs.website/rust/std",
+                    "2025-02-01T00:00:00Z",
+                    &[],
+                    "home",
+                ),
+            ],
+            BookmarkSource::default(),
+        )
+    }
+
+    fn titles(bookmarks: &Bookmarks) -> Vec<String> {
+        bookmarks
+            .links()
+            .map(|visit| match visit.node {
+                BookmarkNode::Link { title, .. } => title.clone(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn find_duplicates_groups_by_url_and_title() {
+        let bookmarks = sample();
+        let report = find_duplicates(&bookmarks)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "2 copies of https://test.website/\n  \
+                    /Dev  \"Test\"  added 2025-03-01  https://test.website/\n  \
+                    /Reading  \"Test website\"  added 2025-04-01  HTTPS://Test.Website/?utm_source=feed#top",
+                "2 similar titles on docs.website: \"rust docs\"\n  \
+                    /Dev  \"Rust Docs\"  added 2025-03-01  https://www.docs.website/rust\n  \
+                    /  \"rust docs!\"  added 2025-02-01  https://docs.website/rust/std",
+            ],
+            report
+        );
+    }
+
+    #[test]
+    fn dedupe_strategies_work() {
+        let mut first = sample();
+        assert_eq!(1, dedupe(&mut first, DuplicateStrategy::First));
+        assert_eq!(vec!["Test", "Rust Docs", "rust docs!"], titles(&first));
+
+        let mut newest = sample();
+        assert_eq!(1, dedupe(&mut newest, DuplicateStrategy::Newest));
+        assert_eq!(
+            vec!["Rust Docs", "Test website", "rust docs!"],
+            titles(&newest)
+        );
+
+        let mut merged = sample();
+        assert_eq!(1, dedupe(&mut merged, DuplicateStrategy::Merge));
+        let expected = BookmarkNode::Link {
+            title: "Test".to_string(),
+            url: "https://test.website/".to_string(),
+            add_date: "2025-03-01T00:00:00Z".parse().unwrap(),
+            last_modified: "2025-03-01T00:00:00Z".parse().unwrap(),
+            tags: Some(vec!["test".to_string(), "later".to_string()]),
+            meta: BookmarkMeta {
+                sources: vec!["work".to_string(), "home".to_string()],
+                folders: vec!["/Dev".to_string(), "/Reading".to_string()],
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            Some(&expected),
+            merged.links().next().map(|visit| visit.node)
+        );
+        assert_eq!(vec!["Test", "Rust Docs", "rust docs!"], titles(&merged));
+    }
+
+    #[test]
+    fn dedupe_keeps_every_source_whatever_the_strategy() {
+        let work = Bookmarks::new(
+            vec![folder(
+                "Dev",
+                vec![link(
+                    "Test",
+                    "https://test.website/",
+                    "2025-04-01T00:00:00Z",
+                    &["work"],
+                    "work",
+                )],
+            )],
+            BookmarkSource::default(),
+        );
+        let home = Bookmarks::new(
+            vec![link(
+                "Test",
+                "https://test.website/?utm_source=feed",
+                "2025-03-01T00:00:00Z",
+                &["home"],
+                "home",
+            )],
+            BookmarkSource::default(),
+        );
+        let mut merged = merge_trees(vec![work, home]).unwrap();
+
+        assert_eq!(1, dedupe(&mut merged, DuplicateStrategy::First));
+        let expected = BookmarkNode::Link {
+            title: "Test".to_string(),
+            url: "https://test.website/".to_string(),
+            add_date: "2025-03-01T00:00:00Z".parse().unwrap(),
+            last_modified: "2025-04-01T00:00:00Z".parse().unwrap(),
+            tags: Some(vec!["work".to_string()]),
+            meta: BookmarkMeta {
+                sources: vec!["work".to_string(), "home".to_string()],
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            vec![&expected],
+            merged.links().map(|visit| visit.node).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use thiserror::Error;
+
+/// Why a node couldn't be converted into a `BookmarkNode`
+///
+/// `path` locates the offending node in the source JSON, e.g. `roots.other.children[3]`.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum ConversionError {
+    #[error("{}: invalid {field} \"{value}\"", display_path(path))]
+    InvalidTimestamp {
+        path: String,
+        field: &'static str,
+        value: String,
+    },
+    #[error("{}: expected a folder at the root, found a URL", display_path(path))]
+    UrlAtRoot { path: String },
+    #[error("{}: {message}", display_path(path))]
+    InvalidNode { path: String, message: String },
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "(root)"
+    } else {
+        path
+    }
+}
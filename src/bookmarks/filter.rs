@@ -0,0 +1,315 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use jiff::Timestamp;
+use url::Url;
+
+use crate::bookmarks::model::{BookmarkNode, Bookmarks};
+
+/// Which bookmarks make it into the vault; every empty field lets everything through
+#[derive(Debug, Clone, Default)]
+pub struct FilterOptions {
+    /// Globs matched against each bookmark's folder path followed by its title, e.g.
+    /// `toolbar/Dev/**`. Built-in roots are named as in [`RootKind`](super::model::RootKind).
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Bookmarks must have at least one of these tags
+    pub tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    /// Domains, which also match their subdomains, or globs such as `*.example.*`
+    pub domains: Vec<String>,
+    pub exclude_domains: Vec<String>,
+    /// Inclusive
+    pub added_after: Option<Timestamp>,
+    /// Exclusive
+    pub added_before: Option<Timestamp>,
+}
+
+/// [`FilterOptions`] with the patterns compiled
+pub struct BookmarkFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    tags: Vec<String>,
+    exclude_tags: Vec<String>,
+    domains: Option<GlobSet>,
+    exclude_domains: GlobSet,
+    added_after: Option<Timestamp>,
+    added_before: Option<Timestamp>,
+}
+
+impl BookmarkFilter {
+    pub fn new(opts: &FilterOptions) -> Result<Self> {
+        let lowercase = |tags: &[String]| tags.iter().map(|tag| tag.to_lowercase()).collect();
+        Ok(Self {
+            include: optional_glob_set(&opts.include)?,
+            exclude: glob_set(&opts.exclude)?,
+            tags: lowercase(&opts.tags),
+            exclude_tags: lowercase(&opts.exclude_tags),
+            domains: optional_glob_set(&opts.domains)?,
+            exclude_domains: glob_set(&opts.exclude_domains)?,
+            added_after: opts.added_after,
+            added_before: opts.added_before,
+        })
+    }
+
+    /// Removes every bookmark the filter doesn't let through, along with the folders that leaves
+    /// empty, and returns how many bookmarks were removed
+    pub fn apply(&self, bookmarks: &mut Bookmarks) -> usize {
+        self.retain(&mut bookmarks.roots, &mut vec![])
+    }
+
+    fn retain(&self, nodes: &mut Vec<BookmarkNode>, path: &mut Vec<String>) -> usize {
+        let mut removed = 0;
+        nodes.retain_mut(|node| match node {
+            BookmarkNode::Link {
+                title,
+                url,
+                add_date,
+                tags,
+                ..
+            } => {
+                path.push(title.clone());
+                let keep = self.keeps(&path.join("/"), url, tags.as_deref(), *add_date);
+                path.pop();
+                removed += usize::from(!keep);
+                keep
+            }
+            BookmarkNode::Folder {
+                title,
+                children,
+                meta,
+                ..
+            } => {
+                let segment = match meta.root_kind() {
+                    Some(kind) if path.is_empty() => {
+                        kind.to_possible_value().unwrap().get_name().to_string()
+                    }
+                    _ => title.clone(),
+                };
+                path.push(segment);
+                let removed_here = self.retain(children, path);
+                path.pop();
+                removed += removed_here;
+                removed_here == 0
+                    || children
+                        .iter()
+                        .any(|child| !matches!(child, BookmarkNode::Separator { .. }))
+            }
+            BookmarkNode::Separator { .. } => true,
+        });
+        removed
+    }
+
+    fn keeps(&self, path: &str, url: &str, tags: Option<&[String]>, add_date: Timestamp) -> bool {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase));
+        let has_tag = |wanted: &[String]| {
+            tags.unwrap_or_default()
+                .iter()
+                .any(|tag| wanted.contains(&tag.to_lowercase()))
+        };
+        let on_domain = |domains: &GlobSet| {
+            host.as_deref()
+                .is_some_and(|host| matches_domain(domains, host))
+        };
+
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.is_match(path))
+            && !self.exclude.is_match(path)
+            && (self.tags.is_empty() || has_tag(&self.tags))
+            && !has_tag(&self.exclude_tags)
+            && self.domains.as_ref().is_none_or(on_domain)
+            && !on_domain(&self.exclude_domains)
+            && self.added_after.is_none_or(|after| add_date >= after)
+            && self.added_before.is_none_or(|before| add_date < before)
+    }
+}
+
+/// Whether the host, or any domain it's a subdomain of, matches
+fn matches_domain(domains: &GlobSet, host: &str) -> bool {
+    std::iter::successors(Some(host), |host| {
+        host.split_once('.').map(|(_, parent)| parent)
+    })
+    .any(|domain| domains.is_match(domain))
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("Invalid pattern \"{pattern}\""))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+/// A glob set that's only there if there are patterns, since an empty one matches nothing
+fn optional_glob_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+    (!patterns.is_empty())
+        .then(|| glob_set(patterns))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmarks::model::{BookmarkMeta, BookmarkSource};
+    use pretty_assertions::assert_eq;
+
+    fn link(title: &str, url: &str, added: &str, tags: &[&str]) -> BookmarkNode {
+        let add_date = added.parse().unwrap();
+        BookmarkNode::Link {
+            title: title.to_string(),
+            url: url.to_string(),
+            add_date,
+            last_modified: add_date,
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            meta: BookmarkMeta::default(),
+        }
+    }
+
+    fn folder(title: &str, root: Option<&str>, children: Vec<BookmarkNode>) -> BookmarkNode {
+        BookmarkNode::Folder {
+            title: title.to_string(),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            children,
+            meta: BookmarkMeta {
+                root: root.map(|root| root.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn sample() -> Bookmarks {
+        Bookmarks::new(
+            vec![
+                folder(
+                    "Bookmarks bar",
+                    Some("bookmark_bar"),
+                    vec![
+                        folder(
+                            "Dev",
+                            None,
+                            vec![
+                                link(
+                                    "Rust",
+                                    "https://www.rust-lang.org/",
+                                    "2024-06-01T00:00:00Z",
+                                    &["Rust", "lang"],
+                                ),
+                                link(
+                                    "Docs",
+                                    "https://docs.rs/",
+                                    "2023-06-01T00:00:00Z",
+                                    &["rust"],
+                                ),
+                            ],
+                        ),
+                        folder(
+                            "News",
+                            None,
+                            vec![link(
+                                "Daily",
+                                "https://news.example.com/",
+                                "2024-02-01T00:00:00Z",
+                                &[],
+                            )],
+                        ),
+                        folder("Empty", None, vec![]),
+                    ],
+                ),
+                link("Loose", "https://example.com/", "2025-01-01T00:00:00Z", &[]),
+            ],
+            BookmarkSource::default(),
+        )
+    }
+
+    /// Applies the filter and returns the removed count and the path of every remaining node
+    fn filtered(opts: FilterOptions) -> (usize, Vec<String>) {
+        let mut bookmarks = sample();
+        let removed = BookmarkFilter::new(&opts).unwrap().apply(&mut bookmarks);
+        let paths = bookmarks
+            .depth_first()
+            .map(|visit| {
+                let title = match visit.node {
+                    BookmarkNode::Link { title, .. } | BookmarkNode::Folder { title, .. } => title,
+                    BookmarkNode::Separator { .. } => "-",
+                };
+                [visit.path.join("/"), title.to_string()].join("/")
+            })
+            .collect();
+        (removed, paths)
+    }
+
+    #[test]
+    fn no_filters_keep_everything() {
+        let (removed, paths) = filtered(FilterOptions::default());
+        assert_eq!(0, removed);
+        assert_eq!(8, paths.len());
+    }
+
+    #[test]
+    fn folder_globs_work_and_prune_empty_folders() {
+        let (removed, paths) = filtered(FilterOptions {
+            include: vec!["toolbar/dev/**".to_string()],
+            exclude: vec!["**/Docs".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(3, removed);
+        // "Empty" was empty to begin with, so it's left alone
+        assert_eq!(
+            vec![
+                "/Bookmarks bar",
+                "Bookmarks bar/Dev",
+                "Bookmarks bar/Dev/Rust",
+                "Bookmarks bar/Empty",
+            ],
+            paths
+        );
+    }
+
+    #[test]
+    fn tag_domain_and_date_filters_work() {
+        let (_, paths) = filtered(FilterOptions {
+            tags: vec!["RUST".to_string()],
+            exclude_tags: vec!["lang".to_string()],
+            ..Default::default()
+        });
+        assert!(paths.contains(&"Bookmarks bar/Dev/Docs".to_string()));
+        assert!(!paths.contains(&"Bookmarks bar/Dev/Rust".to_string()));
+
+        let (removed, paths) = filtered(FilterOptions {
+            domains: vec!["example.com".to_string(), "*.rust-lang.*".to_string()],
+            exclude_domains: vec!["news.example.com".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(2, removed);
+        assert!(paths.contains(&"/Loose".to_string()));
+        assert!(paths.contains(&"Bookmarks bar/Dev/Rust".to_string()));
+
+        let (removed, paths) = filtered(FilterOptions {
+            added_after: Some("2024-01-01T00:00:00Z".parse().unwrap()),
+            added_before: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        });
+        assert_eq!(2, removed);
+        assert!(paths.contains(&"Bookmarks bar/News/Daily".to_string()));
+        assert!(!paths.contains(&"/Loose".to_string()));
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported() {
+        let opts = FilterOptions {
+            include: vec!["[".to_string()],
+            ..Default::default()
+        };
+        let err = BookmarkFilter::new(&opts).err().unwrap();
+        assert_eq!("Invalid pattern \"[\"", err.to_string());
+    }
+}
@@ -0,0 +1,315 @@
+use anyhow::Result;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+pub mod mozlz4;
+pub mod places;
+
+/// Firefox exports refer to their bookmarks as "places" and folders as "place containers"
+///
+/// Nodes are told apart by their `type`, falling back to the numeric `typeCode` for exports that
+/// only carry the latter.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum FirefoxBookmarkNode {
+    #[serde(rename = "text/x-moz-place", rename_all = "camelCase")]
+    Place {
+        guid: String,
+        #[serde(default)]
+        title: String,
+        index: u32,
+        date_added: u64,
+        last_modified: u64,
+        id: u64,
+        tags: Option<String>,
+        uri: String,
+        keyword: Option<String>,
+        post_data: Option<String>,
+        icon_uri: Option<String>,
+        annos: Option<Vec<FirefoxAnnotation>>,
+    },
+    #[serde(rename = "text/x-moz-place-container", rename_all = "camelCase")]
+    PlaceContainer {
+        guid: String,
+        #[serde(default)]
+        title: String,
+        index: u32,
+        date_added: u64,
+        last_modified: u64,
+        id: u64,
+        /// Which built-in folder this is, e.g. `toolbarFolder`
+        root: Option<String>,
+        annos: Option<Vec<FirefoxAnnotation>>,
+        #[serde(default, deserialize_with = "deserialize_children")]
+        children: Option<Vec<FirefoxBookmarkNode>>,
+    },
+    #[serde(rename = "text/x-moz-place-separator", rename_all = "camelCase")]
+    Separator {
+        guid: String,
+        #[serde(default)]
+        title: String,
+        index: u32,
+        date_added: u64,
+        last_modified: u64,
+        id: u64,
+        annos: Option<Vec<FirefoxAnnotation>>,
+    },
+}
+
+/// A piece of metadata attached to a place, e.g. its description
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FirefoxAnnotation {
+    pub name: String,
+    pub value: Value,
+    #[serde(default)]
+    pub flags: i64,
+    #[serde(default)]
+    pub expires: i64,
+}
+
+/// The annotation holding a bookmark's description in older Firefox versions
+pub const DESCRIPTION_ANNO: &str = "bookmarkProperties/description";
+
+/// Attempts to deserialize JSON data representing Firefox bookmarks
+pub fn bookmarks_from_ff_json(data: &str) -> Result<FirefoxBookmarkNode> {
+    let result = serde_json::from_value(with_type_from_code(serde_json::from_str(data)?))?;
+    Ok(result)
+}
+
+fn deserialize_children<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<FirefoxBookmarkNode>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(children) = Option::<Vec<Value>>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    children
+        .into_iter()
+        .map(|child| serde_json::from_value(with_type_from_code(child)).map_err(D::Error::custom))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Fills in a missing `type` from `typeCode` so the node can be deserialized by its tag
+fn with_type_from_code(mut node: Value) -> Value {
+    if let Value::Object(fields) = &mut node {
+        if !fields.contains_key("type") {
+            let kind = match fields.get("typeCode").and_then(Value::as_u64) {
+                Some(1) => "text/x-moz-place",
+                Some(2) => "text/x-moz-place-container",
+                Some(3) => "text/x-moz-place-separator",
+                _ => return node,
+            };
+            fields.insert("type".to_string(), kind.into());
+        }
+    }
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn container(
+        guid: &str,
+        title: &str,
+        index: u32,
+        dates: (u64, u64),
+        id: u64,
+        root: Option<&str>,
+        children: Option<Vec<FirefoxBookmarkNode>>,
+    ) -> FirefoxBookmarkNode {
+        FirefoxBookmarkNode::PlaceContainer {
+            guid: guid.to_string(),
+            title: title.to_string(),
+            index,
+            date_added: dates.0,
+            last_modified: dates.1,
+            id,
+            root: root.map(|root| root.to_string()),
+            annos: None,
+            children,
+        }
+    }
+
+    fn test_place() -> FirefoxBookmarkNode {
+        FirefoxBookmarkNode::Place {
+            guid: "mAQ-wHKmGwfH".to_string(),
+            title: "Test".to_string(),
+            index: 0,
+            date_added: 1740601105441000,
+            last_modified: 1740601275702000,
+            id: 12,
+            tags: Some("test,test1".to_string()),
+            uri: "https://test.website/".to_string(),
+            keyword: Some("test_keyword".to_string()),
+            post_data: None,
+            icon_uri: None,
+            annos: None,
+        }
+    }
+
+    #[test]
+    fn try_parse_ff_place() {
+        let expected = test_place();
+        let input = r#"{
+    "guid": "mAQ-wHKmGwfH",
+    "title": "Test",
+    "index": 0,
+    "dateAdded": 1740601105441000,
+    "lastModified": 1740601275702000,
+    "id": 12,
+    "typeCode": 1,
+    "tags": "test,test1",
+    "type": "text/x-moz-place",
+    "uri": "https://test.website/",
+    "keyword": "test_keyword",
+    "postData": null
+}"#;
+        assert_eq!(
+            serde_json::from_str::<FirefoxBookmarkNode>(input).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn try_parse_ff_optional_fields_and_separators() {
+        // Untagged places, separators and nodes identified only by `typeCode`
+        let input = r#"{
+    "guid": "toolbar_____",
+    "title": "toolbar",
+    "index": 1,
+    "dateAdded": 1,
+    "lastModified": 2,
+    "id": 3,
+    "typeCode": 2,
+    "root": "toolbarFolder",
+    "children": [
+        {
+            "guid": "untagged____",
+            "index": 0,
+            "dateAdded": 3,
+            "lastModified": 4,
+            "id": 7,
+            "typeCode": 1,
+            "iconUri": "https://untagged.website/favicon.ico",
+            "uri": "https://untagged.website/",
+            "annos": [{"name": "bookmarkProperties/description", "flags": 0, "expires": 4, "value": "A description"}]
+        },
+        {
+            "guid": "separator___",
+            "title": "",
+            "index": 1,
+            "dateAdded": 5,
+            "lastModified": 6,
+            "id": 8,
+            "typeCode": 3,
+            "type": "text/x-moz-place-separator"
+        }
+    ]
+}"#;
+        let expected = container(
+            "toolbar_____",
+            "toolbar",
+            1,
+            (1, 2),
+            3,
+            Some("toolbarFolder"),
+            Some(vec![
+                FirefoxBookmarkNode::Place {
+                    guid: "untagged____".to_string(),
+                    title: "".to_string(),
+                    index: 0,
+                    date_added: 3,
+                    last_modified: 4,
+                    id: 7,
+                    tags: None,
+                    uri: "https://untagged.website/".to_string(),
+                    keyword: None,
+                    post_data: None,
+                    icon_uri: Some("https://untagged.website/favicon.ico".to_string()),
+                    annos: Some(vec![FirefoxAnnotation {
+                        name: DESCRIPTION_ANNO.to_string(),
+                        value: "A description".into(),
+                        flags: 0,
+                        expires: 4,
+                    }]),
+                },
+                FirefoxBookmarkNode::Separator {
+                    guid: "separator___".to_string(),
+                    title: "".to_string(),
+                    index: 1,
+                    date_added: 5,
+                    last_modified: 6,
+                    id: 8,
+                    annos: None,
+                },
+            ]),
+        );
+        assert_eq!(expected, bookmarks_from_ff_json(input).unwrap());
+    }
+
+    #[test]
+    fn deserialize_ff_bookmarks() {
+        let input = include_str!("../../../example-firefox.json");
+        let expected = container(
+            "root________",
+            "",
+            0,
+            (1740601048186000, 1740601275702000),
+            1,
+            Some("placesRoot"),
+            Some(vec![
+                container(
+                    "menu________",
+                    "menu",
+                    0,
+                    (1740601048186000, 1740601151666000),
+                    2,
+                    Some("bookmarksMenuFolder"),
+                    None,
+                ),
+                container(
+                    "toolbar_____",
+                    "toolbar",
+                    1,
+                    (1740601048186000, 1740601048270000),
+                    3,
+                    Some("toolbarFolder"),
+                    None,
+                ),
+                container(
+                    "unfiled_____",
+                    "unfiled",
+                    3,
+                    (1740601048186000, 1740601275702000),
+                    5,
+                    Some("unfiledBookmarksFolder"),
+                    Some(vec![container(
+                        "KYvW-dlvB_YS",
+                        "Test Folder",
+                        0,
+                        (1740601261107000, 1740601275702000),
+                        17,
+                        None,
+                        Some(vec![test_place()]),
+                    )]),
+                ),
+                container(
+                    "mobile______",
+                    "mobile",
+                    4,
+                    (1740601048206000, 1740601048270000),
+                    6,
+                    Some("mobileFolder"),
+                    None,
+                ),
+            ]),
+        );
+        let result = bookmarks_from_ff_json(input).unwrap();
+        assert_eq!(result, expected);
+    }
+}
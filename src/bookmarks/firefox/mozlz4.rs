@@ -0,0 +1,127 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+/// Magic number at the start of every mozLz4 file (`.jsonlz4`, `.baklz4`, ...)
+const MAGIC: &[u8] = b"mozLz40\0";
+
+/// LZ4 can't expand a block by more than this, which bounds the size a header can honestly claim
+const MAX_RATIO: usize = 255;
+
+/// Whether the data is mozLz4-compressed
+pub fn is_mozlz4(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Decompresses a mozLz4 file: the magic number, the decompressed size as a little-endian `u32`,
+/// then a single LZ4 block
+pub fn decompress_mozlz4(data: &[u8]) -> Result<Vec<u8>> {
+    let Some(rest) = data.strip_prefix(MAGIC) else {
+        bail!("Missing mozLz4 magic number");
+    };
+    let Some((size, block)) = rest.split_first_chunk::<4>() else {
+        bail!("Truncated mozLz4 header");
+    };
+    let size = u32::from_le_bytes(*size) as usize;
+    // Checked before the size is used to allocate the output
+    if size > block.len().saturating_mul(MAX_RATIO) {
+        bail!(
+            "Corrupt mozLz4 data: a {}-byte block can't hold {size} bytes",
+            block.len()
+        );
+    }
+    let decompressed = lz4_flex::block::decompress(block, size).context("Corrupt mozLz4 data")?;
+    if decompressed.len() != size {
+        bail!(
+            "Corrupt mozLz4 data: expected {size} bytes, got {}",
+            decompressed.len()
+        );
+    }
+    Ok(decompressed)
+}
+
+/// Finds the most recent backup in a Firefox `bookmarkbackups` directory
+///
+/// Backups are named `bookmarks-YYYY-MM-DD_<count>_<hash>.jsonlz4`, so the date in the name is
+/// compared first and the modification time only breaks ties.
+pub fn newest_backup(dir: &Path) -> Result<PathBuf> {
+    let mut newest = None;
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let is_backup = name.starts_with("bookmarks-")
+            && (name.ends_with(".jsonlz4") || name.ends_with(".json"));
+        if !is_backup || !entry.file_type()?.is_file() {
+            continue;
+        }
+        let date = name
+            .trim_start_matches("bookmarks-")
+            .chars()
+            .take("YYYY-MM-DD".len())
+            .collect::<String>();
+        let key = (date, entry.metadata()?.modified()?);
+        if newest.as_ref().is_none_or(|(newest, _)| key > *newest) {
+            newest = Some((key, entry.path()));
+        }
+    }
+    match newest {
+        Some((_, path)) => Ok(path),
+        None => bail!("No bookmark backups found in {}", dir.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn compress_mozlz4(data: &[u8]) -> Vec<u8> {
+        let mut compressed = MAGIC.to_vec();
+        compressed.extend((data.len() as u32).to_le_bytes());
+        compressed.extend(lz4_flex::block::compress(data));
+        compressed
+    }
+
+    #[test]
+    fn decompress_mozlz4_works() {
+        let input = include_bytes!("../../../example-firefox.json");
+        let compressed = compress_mozlz4(input);
+        assert!(is_mozlz4(&compressed));
+        assert_eq!(input.to_vec(), decompress_mozlz4(&compressed).unwrap());
+    }
+
+    #[test]
+    fn decompress_mozlz4_rejects_bad_input() {
+        assert!(decompress_mozlz4(b"{}").is_err());
+        assert!(decompress_mozlz4(b"mozLz40\0\x01").is_err());
+        assert!(decompress_mozlz4(b"mozLz40\0\xff\x00\x00\x00garbage").is_err());
+        let huge = decompress_mozlz4(b"mozLz40\0\xff\xff\xff\xff\x00").unwrap_err();
+        assert_eq!(
+            "Corrupt mozLz4 data: a 1-byte block can't hold 4294967295 bytes",
+            huge.to_string()
+        );
+    }
+
+    #[test]
+    fn newest_backup_works() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "bookmarks-2025-02-25_4_abc.jsonlz4",
+            "bookmarks-2025-03-01_5_def.jsonlz4",
+            "bookmarks-2025-02-28_5_ghi.jsonlz4",
+            "not-a-backup.jsonlz4",
+        ] {
+            fs::write(dir.path().join(name), []).unwrap();
+        }
+        assert_eq!(
+            dir.path().join("bookmarks-2025-03-01_5_def.jsonlz4"),
+            newest_backup(dir.path()).unwrap()
+        );
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(newest_backup(empty.path()).is_err());
+    }
+}
@@ -0,0 +1,419 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{types::Value as SqlValue, Connection, OpenFlags};
+use serde_json::Value;
+use tempfile::TempDir;
+
+use super::{FirefoxAnnotation, FirefoxBookmarkNode};
+
+const TYPE_BOOKMARK: i64 = 1;
+const TYPE_SEPARATOR: i64 = 3;
+const ROOT_GUID: &str = "root________";
+const TAGS_GUID: &str = "tags________";
+
+/// The `root` names JSON backups give the built-in folders
+const ROOT_NAMES: [(&str, &str); 5] = [
+    (ROOT_GUID, "placesRoot"),
+    ("menu________", "bookmarksMenuFolder"),
+    ("toolbar_____", "toolbarFolder"),
+    ("unfiled_____", "unfiledBookmarksFolder"),
+    ("mobile______", "mobileFolder"),
+];
+
+/// A row of `moz_bookmarks` joined with the URL of the place it points to
+struct BookmarkRow {
+    id: i64,
+    kind: i64,
+    parent: i64,
+    position: i64,
+    title: String,
+    date_added: i64,
+    last_modified: i64,
+    guid: String,
+    place_id: Option<i64>,
+    url: Option<String>,
+    keyword: Option<String>,
+    post_data: Option<String>,
+}
+
+/// Reads the bookmarks out of a Firefox profile's `places.sqlite`
+///
+/// Firefox holds the database open (and locked) while it runs, so it is never opened for writing.
+/// Without a write-ahead log the file is opened in immutable mode, otherwise the database and its
+/// log are copied aside so that bookmarks Firefox hasn't checkpointed yet aren't missed.
+///
+/// Annotations such as descriptions are read from `moz_items_annos`, as JSON backups include them.
+pub fn bookmarks_from_places(path: &Path) -> Result<FirefoxBookmarkNode> {
+    let (connection, _snapshot) = if wal_path(path).exists() {
+        let (connection, snapshot) = open_snapshot(path)?;
+        (connection, Some(snapshot))
+    } else {
+        (open_immutable(path)?, None)
+    };
+    read_tree(&connection)
+        .with_context(|| format!("Failed to read bookmarks from {}", path.display()))
+}
+
+fn wal_path(path: &Path) -> std::path::PathBuf {
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    wal.into()
+}
+
+fn open_immutable(path: &Path) -> Result<Connection> {
+    // Characters with a meaning in URIs have to be escaped in the file name
+    let escaped = path
+        .to_string_lossy()
+        .replace('%', "%25")
+        .replace('?', "%3f")
+        .replace('#', "%23");
+    let connection = Connection::open_with_flags(
+        format!("file:{escaped}?immutable=1"),
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+    )
+    .with_context(|| format!("Failed to open {}", path.display()))?;
+    Ok(connection)
+}
+
+/// Copies the database and its write-ahead log to a temporary directory and opens the copy
+fn open_snapshot(path: &Path) -> Result<(Connection, TempDir)> {
+    let snapshot = tempfile::tempdir()?;
+    let copy = snapshot.path().join("places.sqlite");
+    fs::copy(path, &copy).with_context(|| format!("Failed to snapshot {}", path.display()))?;
+    fs::copy(wal_path(path), wal_path(&copy))
+        .with_context(|| format!("Failed to snapshot {}", wal_path(path).display()))?;
+    let connection = Connection::open(&copy)
+        .with_context(|| format!("Failed to open snapshot of {}", path.display()))?;
+    Ok((connection, snapshot))
+}
+
+fn read_tree(connection: &Connection) -> Result<FirefoxBookmarkNode> {
+    let mut statement = connection.prepare(
+        "SELECT b.id, b.type, b.parent, b.position, b.title, b.dateAdded, b.lastModified, b.guid,
+                b.fk, p.url, k.keyword, k.post_data
+         FROM moz_bookmarks b
+         LEFT JOIN moz_places p ON p.id = b.fk
+         LEFT JOIN moz_keywords k ON k.id = (
+             SELECT id FROM moz_keywords WHERE place_id = b.fk ORDER BY id LIMIT 1
+         )
+         ORDER BY b.parent, b.position",
+    )?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok(BookmarkRow {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                parent: row.get::<_, Option<i64>>(2)?.unwrap_or_default(),
+                position: row.get::<_, Option<i64>>(3)?.unwrap_or_default(),
+                title: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                date_added: row.get::<_, Option<i64>>(5)?.unwrap_or_default(),
+                last_modified: row.get::<_, Option<i64>>(6)?.unwrap_or_default(),
+                guid: row.get(7)?,
+                place_id: row.get(8)?,
+                url: row.get(9)?,
+                keyword: row.get(10)?,
+                post_data: row.get(11)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let Some(root) = rows.iter().find(|row| row.guid == ROOT_GUID) else {
+        bail!("No bookmarks root ({ROOT_GUID}) found");
+    };
+    let tags_root = rows
+        .iter()
+        .find(|row| row.guid == TAGS_GUID)
+        .map(|row| row.id);
+
+    let mut children: HashMap<i64, Vec<&BookmarkRow>> = HashMap::new();
+    for row in &rows {
+        children.entry(row.parent).or_default().push(row);
+    }
+
+    // Tags are folders under the tags root, each holding a bookmark for every place tagged with it
+    let mut tags: HashMap<i64, Vec<&str>> = HashMap::new();
+    if let Some(tags_root) = tags_root {
+        for tag in children.get(&tags_root).into_iter().flatten() {
+            for tagged in children.get(&tag.id).into_iter().flatten() {
+                if let Some(place_id) = tagged.place_id {
+                    tags.entry(place_id).or_default().push(&tag.title);
+                }
+            }
+        }
+    }
+
+    let annos = read_annos(connection)?;
+    build_node(root, &children, &tags, &annos, tags_root)
+}
+
+/// Reads `moz_items_annos` (e.g. descriptions), by bookmark id, in the shape JSON backups use
+///
+/// Profiles created by recent Firefox versions may not have the table at all.
+fn read_annos(connection: &Connection) -> Result<HashMap<i64, Vec<FirefoxAnnotation>>> {
+    let exists = connection
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'moz_items_annos'")?
+        .exists([])?;
+    if !exists {
+        return Ok(HashMap::new());
+    }
+    let mut statement = connection.prepare(
+        "SELECT a.item_id, n.name, a.content, a.flags, a.expiration
+         FROM moz_items_annos a
+         JOIN moz_anno_attributes n ON n.id = a.anno_attribute_id
+         ORDER BY a.item_id, n.name",
+    )?;
+    let rows = statement.query_map([], |row| {
+        let value = match row.get::<_, SqlValue>(2)? {
+            SqlValue::Text(text) => Value::from(text),
+            SqlValue::Integer(number) => Value::from(number),
+            SqlValue::Real(number) => Value::from(number),
+            SqlValue::Null | SqlValue::Blob(_) => Value::Null,
+        };
+        let anno = FirefoxAnnotation {
+            name: row.get(1)?,
+            value,
+            flags: row.get::<_, Option<i64>>(3)?.unwrap_or_default(),
+            expires: row.get::<_, Option<i64>>(4)?.unwrap_or_default(),
+        };
+        Ok((row.get::<_, i64>(0)?, anno))
+    })?;
+    let mut annos: HashMap<i64, Vec<FirefoxAnnotation>> = HashMap::new();
+    for row in rows {
+        let (item_id, anno) = row?;
+        annos.entry(item_id).or_default().push(anno);
+    }
+    Ok(annos)
+}
+
+fn build_node(
+    row: &BookmarkRow,
+    children: &HashMap<i64, Vec<&BookmarkRow>>,
+    tags: &HashMap<i64, Vec<&str>>,
+    annos: &HashMap<i64, Vec<FirefoxAnnotation>>,
+    tags_root: Option<i64>,
+) -> Result<FirefoxBookmarkNode> {
+    let node_annos = annos.get(&row.id).cloned();
+    let date_added = u64::try_from(row.date_added)
+        .with_context(|| format!("Bookmark {} has a negative dateAdded", row.guid))?;
+    let last_modified = u64::try_from(row.last_modified)
+        .with_context(|| format!("Bookmark {} has a negative lastModified", row.guid))?;
+
+    let index = u32::try_from(row.position)
+        .with_context(|| format!("Bookmark {} has an invalid position", row.guid))?;
+    let id = u64::try_from(row.id)
+        .with_context(|| format!("Bookmark {} has an invalid id", row.guid))?;
+
+    let node = match row.kind {
+        TYPE_BOOKMARK => FirefoxBookmarkNode::Place {
+            guid: row.guid.clone(),
+            title: row.title.clone(),
+            index,
+            date_added,
+            last_modified,
+            id,
+            tags: row
+                .place_id
+                .and_then(|place_id| tags.get(&place_id))
+                .map(|tags| tags.join(",")),
+            uri: row.url.clone().unwrap_or_default(),
+            keyword: row.keyword.clone(),
+            post_data: row.post_data.clone(),
+            // Favicons live in a separate database (favicons.sqlite)
+            icon_uri: None,
+            annos: node_annos,
+        },
+        TYPE_SEPARATOR => FirefoxBookmarkNode::Separator {
+            guid: row.guid.clone(),
+            title: row.title.clone(),
+            index,
+            date_added,
+            last_modified,
+            id,
+            annos: node_annos,
+        },
+        _ => {
+            let children = children
+                .get(&row.id)
+                .into_iter()
+                .flatten()
+                // The tags root is folded into each place's `tags` instead
+                .filter(|child| Some(child.id) != tags_root)
+                .map(|child| build_node(child, children, tags, annos, tags_root))
+                .collect::<Result<Vec<_>>>()?;
+            FirefoxBookmarkNode::PlaceContainer {
+                guid: row.guid.clone(),
+                title: row.title.clone(),
+                index,
+                date_added,
+                last_modified,
+                id,
+                root: ROOT_NAMES
+                    .iter()
+                    .find(|(guid, _)| *guid == row.guid)
+                    .map(|(_, root)| root.to_string()),
+                annos: node_annos,
+                // Backups leave `children` out entirely for empty folders
+                children: (!children.is_empty()).then_some(children),
+            }
+        }
+    };
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmarks::{
+        firefox::bookmarks_from_ff_json,
+        model::{BookmarkNode, Bookmarks},
+    };
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+
+    /// Builds a cut-down `places.sqlite` mirroring `example-firefox.json`
+    fn create_fixture(path: &Path) -> Connection {
+        let connection = Connection::open(path).unwrap();
+        connection
+            .execute_batch(
+                r#"
+CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url LONGVARCHAR, title LONGVARCHAR);
+CREATE TABLE moz_keywords (
+    id INTEGER PRIMARY KEY AUTOINCREMENT, keyword TEXT UNIQUE, place_id INTEGER, post_data TEXT
+);
+CREATE TABLE moz_bookmarks (
+    id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER DEFAULT NULL, parent INTEGER,
+    position INTEGER, title LONGVARCHAR, keyword_id INTEGER, folder_type TEXT,
+    dateAdded INTEGER, lastModified INTEGER, guid TEXT
+);
+INSERT INTO moz_places VALUES (1, 'https://test.website/', 'Test');
+INSERT INTO moz_keywords VALUES (1, 'test_keyword', 1, NULL);
+INSERT INTO moz_bookmarks VALUES
+    (1, 2, NULL, 0, 0, '', NULL, NULL, 1740601048186000, 1740601275702000, 'root________'),
+    (2, 2, NULL, 1, 0, 'menu', NULL, NULL, 1740601048186000, 1740601151666000, 'menu________'),
+    (3, 2, NULL, 1, 1, 'toolbar', NULL, NULL, 1740601048186000, 1740601048270000, 'toolbar_____'),
+    (4, 2, NULL, 1, 2, 'tags', NULL, NULL, 1740601048186000, 1740601275702000, 'tags________'),
+    (5, 2, NULL, 1, 3, 'unfiled', NULL, NULL, 1740601048186000, 1740601275702000, 'unfiled_____'),
+    (6, 2, NULL, 1, 4, 'mobile', NULL, NULL, 1740601048206000, 1740601048270000, 'mobile______'),
+    (7, 2, NULL, 4, 0, 'test', NULL, NULL, 1740601275702000, 1740601275702000, 'tagtest_____'),
+    (8, 1, 1, 7, 0, NULL, NULL, NULL, 1740601275702000, 1740601275702000, 'tagged1_____'),
+    (9, 2, NULL, 4, 1, 'test1', NULL, NULL, 1740601275702000, 1740601275702000, 'tagtest1____'),
+    (10, 1, 1, 9, 0, NULL, NULL, NULL, 1740601275702000, 1740601275702000, 'tagged2_____'),
+    (17, 2, NULL, 5, 0, 'Test Folder', NULL, NULL, 1740601261107000, 1740601275702000, 'KYvW-dlvB_YS'),
+    (12, 1, 1, 17, 0, 'Test', NULL, NULL, 1740601105441000, 1740601275702000, 'mAQ-wHKmGwfH');
+"#,
+            )
+            .unwrap();
+        connection
+    }
+
+    #[test]
+    fn places_matches_json_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("places.sqlite");
+        drop(create_fixture(&path));
+
+        let expected =
+            bookmarks_from_ff_json(include_str!("../../../example-firefox.json")).unwrap();
+        assert_eq!(expected, bookmarks_from_places(&path).unwrap());
+    }
+
+    #[test]
+    fn places_reads_annotations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("places.sqlite");
+        create_fixture(&path)
+            .execute_batch(
+                "CREATE TABLE moz_anno_attributes (id INTEGER PRIMARY KEY, name VARCHAR(32));
+                 CREATE TABLE moz_items_annos (
+                     id INTEGER PRIMARY KEY, item_id INTEGER, anno_attribute_id INTEGER,
+                     content LONGVARCHAR, flags INTEGER, expiration INTEGER, type INTEGER,
+                     dateAdded INTEGER, lastModified INTEGER
+                 );
+                 INSERT INTO moz_anno_attributes VALUES
+                     (1, 'bookmarkProperties/description'),
+                     (2, 'bookmarkProperties/loadInSidebar');
+                 INSERT INTO moz_items_annos VALUES
+                     (1, 12, 1, 'A description', 0, 4, 3, 0, 0),
+                     (2, 12, 2, 1, 0, 4, 1, 0, 0);",
+            )
+            .unwrap();
+
+        let bookmarks = Bookmarks::try_from(bookmarks_from_places(&path).unwrap()).unwrap();
+        let Some(BookmarkNode::Link { meta, .. }) =
+            bookmarks.links().next().map(|visit| visit.node)
+        else {
+            panic!("Expected a link");
+        };
+        assert_eq!(Some("A description"), meta.description.as_deref());
+        assert_eq!(
+            BTreeMap::from([(
+                "bookmarkProperties/loadInSidebar".to_string(),
+                "1".to_string()
+            )]),
+            meta.annotations
+        );
+    }
+
+    #[test]
+    fn places_reads_uncheckpointed_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("places.sqlite");
+        // Keeping this connection open stands in for a running Firefox
+        let firefox = create_fixture(&path);
+        firefox
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 PRAGMA wal_autocheckpoint = 0;
+                 INSERT INTO moz_places VALUES (2, 'https://new.website/', 'New');
+                 INSERT INTO moz_bookmarks VALUES
+                     (14, 1, 2, 3, 0, 'New', NULL, NULL, 1740601300000000, 1740601300000000, 'newbookmark_'),
+                     (15, 3, NULL, 3, 1, NULL, NULL, NULL, 1740601300000000, 1740601300000000, 'separator___');",
+            )
+            .unwrap();
+        assert!(wal_path(&path).exists());
+
+        let FirefoxBookmarkNode::PlaceContainer {
+            children: Some(roots),
+            ..
+        } = bookmarks_from_places(&path).unwrap()
+        else {
+            panic!("Expected the places root to have children");
+        };
+        let FirefoxBookmarkNode::PlaceContainer {
+            title, children, ..
+        } = &roots[1]
+        else {
+            panic!("Expected the toolbar folder");
+        };
+        assert_eq!("toolbar", title);
+        assert_eq!(
+            &Some(vec![
+                FirefoxBookmarkNode::Place {
+                    guid: "newbookmark_".to_string(),
+                    title: "New".to_string(),
+                    index: 0,
+                    date_added: 1740601300000000,
+                    last_modified: 1740601300000000,
+                    id: 14,
+                    tags: None,
+                    uri: "https://new.website/".to_string(),
+                    keyword: None,
+                    post_data: None,
+                    icon_uri: None,
+                    annos: None,
+                },
+                FirefoxBookmarkNode::Separator {
+                    guid: "separator___".to_string(),
+                    title: "".to_string(),
+                    index: 1,
+                    date_added: 1740601300000000,
+                    last_modified: 1740601300000000,
+                    id: 15,
+                    annos: None,
+                }
+            ]),
+            children
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use anyhow::{Context, Result};
+use jiff::Timestamp;
+use scraper::{ElementRef, Html, Node, Selector};
+
+use crate::bookmarks::model::{split_tags, BookmarkMeta, BookmarkNode};
+
+/// The attributes that mark a folder as one of the browser's built-in ones
+const ROOT_ATTRIBUTES: [&str; 2] = ["personal_toolbar_folder", "unfiled_bookmarks_folder"];
+
+/// Attempts to parse a Netscape bookmark file (`<!DOCTYPE NETSCAPE-Bookmark-file-1>`)
+///
+/// Every browser nests folders as `<DT><H3>..</H3><DL><p>..</DL><p>` without closing its `<DT>`
+/// and `<p>` tags, so the list is walked as a flat run of `<DT>`, `<DD>` and `<DL>` elements
+/// rather than trusting the shape of the parsed tree.
+pub fn bookmarks_from_html(data: &str) -> Result<Vec<BookmarkNode>> {
+    let document = Html::parse_document(data);
+    let list = Selector::parse("dl").unwrap();
+
+    // The first <DL> in document order is always the outermost one
+    match document.select(&list).next() {
+        Some(list) => parse_list(list),
+        None => Ok(vec![]),
+    }
+}
+
+fn parse_list(list: ElementRef) -> Result<Vec<BookmarkNode>> {
+    let mut items = vec![];
+    flatten_items(list, &mut items);
+
+    let mut nodes = vec![];
+    let mut items = items.into_iter().peekable();
+    while let Some(item) = items.next() {
+        if item.value().name() != "dt" {
+            continue;
+        }
+        if let Some(heading) = child_element(item, "h3") {
+            // A folder's <DL> is usually inside its <DT>, but a folder description <DD> or an
+            // explicit </DT> pushes it out into the following siblings
+            let mut list = child_element(item, "dl");
+            let mut description = None;
+            if let Some(dd) = items.next_if(|next| next.value().name() == "dd") {
+                list = list.or_else(|| child_element(dd, "dl"));
+                description = Some(element_text(dd)).filter(|description| !description.is_empty());
+            }
+            if list.is_none() {
+                list = items.next_if(|next| next.value().name() == "dl");
+            }
+            nodes.push(parse_folder(heading, list, description)?);
+        } else if let Some(anchor) = child_element(item, "a") {
+            let description = items
+                .next_if(|next| next.value().name() == "dd")
+                .map(element_text)
+                .filter(|description| !description.is_empty());
+            nodes.push(parse_link(anchor, description)?);
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// Collects the <DT>, <DD> and <DL> children of a list, looking through any stray <p> wrappers
+fn flatten_items<'a>(element: ElementRef<'a>, items: &mut Vec<ElementRef<'a>>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        match child.value().name() {
+            "dt" | "dd" | "dl" => items.push(child),
+            "p" => flatten_items(child, items),
+            _ => {}
+        }
+    }
+}
+
+fn parse_folder(
+    heading: ElementRef,
+    list: Option<ElementRef>,
+    description: Option<String>,
+) -> Result<BookmarkNode> {
+    let add_date = parse_seconds(heading, "add_date")?.unwrap_or(Timestamp::UNIX_EPOCH);
+    let last_modified = parse_seconds(heading, "last_modified")?.unwrap_or(add_date);
+    let children = match list {
+        Some(list) => parse_list(list)?,
+        None => vec![],
+    };
+    // Browsers flag their built-in folders, e.g. `PERSONAL_TOOLBAR_FOLDER="true"`
+    let root = ROOT_ATTRIBUTES
+        .iter()
+        .find(|name| heading.value().attr(name) == Some("true"))
+        .map(|name| name.to_string());
+
+    Ok(BookmarkNode::Folder {
+        title: element_text(heading),
+        add_date,
+        last_modified,
+        children,
+        meta: BookmarkMeta {
+            root,
+            description,
+            ..Default::default()
+        },
+    })
+}
+
+fn parse_link(anchor: ElementRef, description: Option<String>) -> Result<BookmarkNode> {
+    let attribute = |name| {
+        anchor
+            .value()
+            .attr(name)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let add_date = parse_seconds(anchor, "add_date")?.unwrap_or(Timestamp::UNIX_EPOCH);
+    let last_modified = parse_seconds(anchor, "last_modified")?.unwrap_or(add_date);
+    let tags = attribute("tags").and_then(|tags| split_tags(tags, |c| c == ','));
+    // Services such as Raindrop.io add their own `DATA-*` attributes (e.g. `DATA-COVER`)
+    let annotations = anchor
+        .value()
+        .attrs()
+        .filter_map(|(name, value)| Some((name.strip_prefix("data-")?.to_string(), value)))
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(name, value)| (name, value.trim().to_string()))
+        .collect();
+
+    Ok(BookmarkNode::Link {
+        title: element_text(anchor),
+        url: attribute("href").unwrap_or_default().to_string(),
+        add_date,
+        last_modified,
+        tags,
+        meta: BookmarkMeta {
+            description,
+            keyword: attribute("shortcuturl").map(|keyword| keyword.to_string()),
+            icon_uri: attribute("icon_uri").map(|icon_uri| icon_uri.to_string()),
+            annotations,
+            ..Default::default()
+        },
+    })
+}
+
+/// Reads a timestamp attribute, which Netscape bookmark files store in seconds since the Unix epoch
+fn parse_seconds(element: ElementRef, name: &str) -> Result<Option<Timestamp>> {
+    let Some(value) = element
+        .value()
+        .attr(name)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    else {
+        return Ok(None);
+    };
+    let seconds = value
+        .parse::<i64>()
+        .with_context(|| format!("Invalid {} \"{value}\"", name.to_uppercase()))?;
+    let timestamp = Timestamp::from_second(seconds)
+        .with_context(|| format!("{} \"{value}\" is out of range", name.to_uppercase()))?;
+    Ok(Some(timestamp))
+}
+
+fn child_element<'a>(element: ElementRef<'a>, name: &str) -> Option<ElementRef<'a>> {
+    element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == name)
+}
+
+/// The trimmed text of an element, ignoring any nested list
+fn element_text(element: ElementRef) -> String {
+    let mut text = String::new();
+    for child in element.children() {
+        match child.value() {
+            Node::Text(fragment) => text.push_str(fragment),
+            Node::Element(nested) if nested.name() != "dl" => {
+                if let Some(nested) = ElementRef::wrap(child) {
+                    text.push_str(&nested.text().collect::<String>());
+                }
+            }
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+
+    fn date_to_ts(date_str: &str) -> Timestamp {
+        date_str.parse::<Timestamp>().unwrap()
+    }
+
+    #[test]
+    fn parse_html_bookmarks() {
+        let input = include_str!("../../simple-bookmarks.html");
+        let expected = vec![BookmarkNode::Folder {
+            title: "Other Bookmarks".to_string(),
+            meta: BookmarkMeta {
+                root: Some("unfiled_bookmarks_folder".to_string()),
+                ..Default::default()
+            },
+            add_date: date_to_ts("2025-02-26T20:17:28Z"),
+            last_modified: date_to_ts("2025-02-26T20:21:15Z"),
+            children: vec![BookmarkNode::Folder {
+                title: "Test Folder".to_string(),
+                meta: BookmarkMeta::default(),
+                add_date: date_to_ts("2025-02-26T20:21:01Z"),
+                last_modified: date_to_ts("2025-02-26T20:21:15Z"),
+                children: vec![BookmarkNode::Link {
+                    title: "Test".to_string(),
+                    url: "https://test.website/".to_string(),
+                    add_date: date_to_ts("2025-02-26T20:18:25Z"),
+                    last_modified: date_to_ts("2025-02-26T20:21:15Z"),
+                    tags: Some(vec!["test".to_string(), "test2".to_string()]),
+                    meta: BookmarkMeta {
+                        keyword: Some("test_keyword".to_string()),
+                        ..Default::default()
+                    },
+                }],
+            }],
+        }];
+        assert_eq!(expected, bookmarks_from_html(input).unwrap());
+    }
+
+    #[test]
+    fn parse_html_descriptions_and_entities() {
+        let input = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1740601048">Tools &amp; Docs</H3>
+    <DD>Folder description
+    <DL><p>
+        <DT><A HREF="https://a.website/?x=1&amp;y=2" ADD_DATE="1740601105">A &lt;b&gt;</A>
+        <DD>Says &quot;hello&quot;
+        <DT><A HREF="https://b.website/">B</A>
+    </DL><p>
+    <DT><H3>Empty</H3>
+    <DL><p>
+    </DL><p>
+    <DT><A HREF="https://c.website/" TAGS="">C</A>
+    <DT><A HREF="https://d.website/" DATA-COVER="https://d.website/cover.png" DATA-IMPORTANT="">D</A>
+</DL><p>
+"#;
+        let link =
+            |title: &str, url: &str, add_date, description: Option<&str>| BookmarkNode::Link {
+                title: title.to_string(),
+                url: url.to_string(),
+                add_date,
+                last_modified: add_date,
+                tags: None,
+                meta: BookmarkMeta {
+                    description: description.map(|description| description.to_string()),
+                    ..Default::default()
+                },
+            };
+        let expected = vec![
+            BookmarkNode::Folder {
+                title: "Tools & Docs".to_string(),
+                meta: BookmarkMeta {
+                    description: Some("Folder description".to_string()),
+                    ..Default::default()
+                },
+                add_date: date_to_ts("2025-02-26T20:17:28Z"),
+                last_modified: date_to_ts("2025-02-26T20:17:28Z"),
+                children: vec![
+                    link(
+                        "A <b>",
+                        "https://a.website/?x=1&y=2",
+                        date_to_ts("2025-02-26T20:18:25Z"),
+                        Some("Says \"hello\""),
+                    ),
+                    link("B", "https://b.website/", Timestamp::UNIX_EPOCH, None),
+                ],
+            },
+            BookmarkNode::Folder {
+                title: "Empty".to_string(),
+                meta: BookmarkMeta::default(),
+                add_date: Timestamp::UNIX_EPOCH,
+                last_modified: Timestamp::UNIX_EPOCH,
+                children: vec![],
+            },
+            link("C", "https://c.website/", Timestamp::UNIX_EPOCH, None),
+            BookmarkNode::Link {
+                title: "D".to_string(),
+                url: "https://d.website/".to_string(),
+                add_date: Timestamp::UNIX_EPOCH,
+                last_modified: Timestamp::UNIX_EPOCH,
+                tags: None,
+                meta: BookmarkMeta {
+                    annotations: BTreeMap::from([(
+                        "cover".to_string(),
+                        "https://d.website/cover.png".to_string(),
+                    )]),
+                    ..Default::default()
+                },
+            },
+        ];
+        assert_eq!(expected, bookmarks_from_html(input).unwrap());
+    }
+
+    #[test]
+    fn parse_html_rejects_bad_dates() {
+        let input = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><A HREF="https://a.website/" ADD_DATE="yesterday">A</A>
+</DL><p>
+"#;
+        assert!(bookmarks_from_html(input).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use jiff::Timestamp;
+use serde::Deserialize;
+
+use crate::bookmarks::model::{BookmarkMeta, BookmarkNode};
+
+/// A bookmark as returned by linkding's REST API (`/api/bookmarks/`)
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LinkdingBookmark {
+    pub id: Option<u64>,
+    pub url: String,
+    /// The user's title, empty when linkding's scraped `website_title` is used instead
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Markdown notes
+    #[serde(default)]
+    pub notes: String,
+    pub website_title: Option<String>,
+    pub website_description: Option<String>,
+    pub favicon_url: Option<String>,
+    #[serde(default)]
+    pub is_archived: bool,
+    #[serde(default)]
+    pub unread: bool,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub tag_names: Vec<String>,
+    /// RFC 3339, e.g. `2025-02-26T20:18:25.441806Z`
+    pub date_added: String,
+    pub date_modified: Option<String>,
+}
+
+/// Either a saved API page or a bare list of its `results`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LinkdingExport {
+    Page { results: Vec<LinkdingBookmark> },
+    List(Vec<LinkdingBookmark>),
+}
+
+/// Attempts to parse linkding's JSON as a flat list of links
+pub fn bookmarks_from_linkding_json(data: &str) -> Result<Vec<BookmarkNode>> {
+    let (LinkdingExport::Page { results: bookmarks } | LinkdingExport::List(bookmarks)) =
+        serde_json::from_str(data)?;
+    let children = bookmarks
+        .into_iter()
+        .enumerate()
+        .map(|(index, bookmark)| {
+            let date = |field: &str, value: &str| {
+                value
+                    .parse::<Timestamp>()
+                    .with_context(|| format!("Invalid {field} \"{value}\" for {}", bookmark.url))
+            };
+            let add_date = date("date_added", &bookmark.date_added)?;
+            let last_modified = match &bookmark.date_modified {
+                Some(date_modified) => date("date_modified", date_modified)?,
+                None => add_date,
+            };
+            let mut annotations = [
+                ("is_archived", bookmark.is_archived),
+                ("unread", bookmark.unread),
+                ("shared", bookmark.shared),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>();
+            if !bookmark.notes.is_empty() {
+                annotations.insert("notes".to_string(), bookmark.notes);
+            }
+            let or_scraped = |value: String, scraped: Option<String>| {
+                Some(value)
+                    .filter(|value| !value.is_empty())
+                    .or(scraped)
+                    .filter(|value| !value.is_empty())
+            };
+
+            Ok(BookmarkNode::Link {
+                title: or_scraped(bookmark.title, bookmark.website_title).unwrap_or_default(),
+                url: bookmark.url,
+                add_date,
+                last_modified,
+                tags: Some(bookmark.tag_names).filter(|tags| !tags.is_empty()),
+                meta: BookmarkMeta {
+                    id: bookmark.id.map(|id| id.to_string()),
+                    index: Some(index as u32),
+                    description: or_scraped(bookmark.description, bookmark.website_description),
+                    icon_uri: bookmark.favicon_url,
+                    annotations,
+                    ..Default::default()
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_linkding_json() {
+        let input = r#"{
+            "count": 1,
+            "next": null,
+            "previous": null,
+            "results": [{
+                "id": 12,
+                "url": "https://test.website/",
+                "title": "",
+                "description": "A test website",
+                "notes": "Worth a read",
+                "website_title": "Test",
+                "website_description": "Scraped description",
+                "favicon_url": null,
+                "is_archived": false,
+                "unread": true,
+                "shared": false,
+                "tag_names": ["test", "test1"],
+                "date_added": "2025-02-26T20:18:25.441806Z",
+                "date_modified": "2025-02-27T08:00:00Z"
+            }]
+        }"#;
+        let expected = vec![BookmarkNode::Link {
+            title: "Test".to_string(),
+            url: "https://test.website/".to_string(),
+            add_date: "2025-02-26T20:18:25.441806Z".parse().unwrap(),
+            last_modified: "2025-02-27T08:00:00Z".parse().unwrap(),
+            tags: Some(vec!["test".to_string(), "test1".to_string()]),
+            meta: BookmarkMeta {
+                id: Some("12".to_string()),
+                index: Some(0),
+                description: Some("A test website".to_string()),
+                annotations: BTreeMap::from([
+                    ("is_archived".to_string(), "false".to_string()),
+                    ("notes".to_string(), "Worth a read".to_string()),
+                    ("shared".to_string(), "false".to_string()),
+                    ("unread".to_string(), "true".to_string()),
+                ]),
+                ..Default::default()
+            },
+        }];
+        assert_eq!(expected, bookmarks_from_linkding_json(input).unwrap());
+
+        let bare = bookmarks_from_linkding_json(
+            r#"[{"url": "https://bare.website/", "date_added": "2025-02-26T20:18:25Z"}]"#,
+        );
+        assert_eq!(1, bare.unwrap().len());
+    }
+}
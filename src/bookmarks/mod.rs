@@ -1,3 +1,534 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use jiff::Timestamp;
+use serde_json::{Map, Value};
+
+use buku::bookmarks_from_buku;
+use canonical::CanonicalOptions;
+use chrome::{bookmarks_from_chrome_json, ChromeBookmarks};
+use firefox::{
+    bookmarks_from_ff_json,
+    mozlz4::{decompress_mozlz4, is_mozlz4, newest_backup},
+    places::bookmarks_from_places,
+};
+use html::bookmarks_from_html;
+use linkding::bookmarks_from_linkding_json;
+use model::{BookmarkSource, Bookmarks, Converter};
+use pinboard::bookmarks_from_pinboard_json;
+use pocket::bookmarks_from_pocket_csv;
+use qutebrowser::{
+    bookmarks_from_qutebrowser_quickmarks, bookmarks_from_qutebrowser_urls, is_qutebrowser_file,
+};
+use raindrop::bookmarks_from_raindrop_csv;
+use safari::bookmarks_from_safari_plist;
+use w3m::{bookmarks_from_w3m_html, LYNX_TITLE, W3M_SECTION_END};
+
+pub mod buku;
+pub mod canonical;
 pub mod chrome;
+pub mod dedupe;
+pub mod error;
+pub mod filter;
 pub mod firefox;
+pub mod html;
+pub mod linkding;
 pub mod model;
+pub mod pinboard;
+pub mod pocket;
+pub mod profiles;
+pub mod qutebrowser;
+pub mod raindrop;
+pub mod safari;
+// A general-purpose API, so not every traversal has a caller in the CLI
+#[allow(dead_code)]
+pub mod traversal;
+pub mod w3m;
+
+/// The bookmark export formats that can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BookmarkFormat {
+    /// Firefox JSON backup, optionally mozLz4-compressed (`.jsonlz4`)
+    Firefox,
+    /// Firefox profile database (`places.sqlite`)
+    Places,
+    /// Chrom(e|ium) `Bookmarks` file
+    Chrome,
+    /// Netscape HTML export, as produced by every browser's "Export bookmarks to HTML"
+    Html,
+    /// Safari `Bookmarks.plist`, binary or XML
+    Safari,
+    /// Pinboard JSON export
+    Pinboard,
+    /// Raindrop.io CSV export (its HTML export is read as `html`)
+    Raindrop,
+    /// Pocket CSV export
+    Pocket,
+    /// linkding JSON, as returned by its bookmarks API
+    Linkding,
+    /// buku database (`bookmarks.db`)
+    Buku,
+    /// qutebrowser `bookmarks/urls`
+    Qutebrowser,
+    /// qutebrowser `quickmarks`
+    Quickmarks,
+    /// w3m `bookmark.html`
+    W3m,
+    /// Lynx `lynx_bookmarks.html`
+    Lynx,
+}
+
+impl BookmarkFormat {
+    /// Guesses the format of an export by sniffing its contents
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        Self::value_variants()
+            .iter()
+            .copied()
+            .find(|format| format.matches(data))
+    }
+
+    /// What detection looks for, used to explain a failed detection
+    fn signature(self) -> &'static str {
+        match self {
+            Self::Firefox => {
+                "Firefox JSON (a root object with \"type\": \"text/x-moz-place-container\")"
+            }
+            Self::Places => "Firefox places.sqlite (an SQLite database with a moz_bookmarks table)",
+            Self::Chrome => {
+                "Chrom(e|ium) JSON (a root object with \"checksum\" and \"roots\" keys)"
+            }
+            Self::Html => "Netscape HTML (a <!DOCTYPE NETSCAPE-Bookmark-file-1> header)",
+            Self::Safari => "Safari property list (a plist mentioning WebBookmarkTypeList)",
+            Self::Pinboard => "Pinboard JSON (an array of objects with \"href\" and \"time\" keys)",
+            Self::Raindrop => "Raindrop.io CSV (a header with url, folder and excerpt columns)",
+            Self::Pocket => "Pocket CSV (a header with url and time_added columns)",
+            Self::Buku => "buku database (an SQLite database with a bookmarks table)",
+            Self::Qutebrowser => "qutebrowser bookmarks (lines starting with a URL)",
+            Self::Quickmarks => "qutebrowser quickmarks (lines ending with a URL)",
+            Self::W3m => "w3m bookmarks (HTML with \"End of section\" comments)",
+            Self::Lynx => "Lynx bookmarks (HTML titled \"Bookmark file\")",
+            Self::Linkding => {
+                "linkding JSON (objects with \"url\" and \"date_added\" keys, optionally under \"results\")"
+            }
+        }
+    }
+
+    fn matches(self, data: &[u8]) -> bool {
+        match self {
+            Self::Firefox => json_object(data).is_some_and(|root| {
+                root.get("type").and_then(Value::as_str) == Some("text/x-moz-place-container")
+            }),
+            Self::Places => {
+                data.starts_with(b"SQLite format 3\0")
+                    && data
+                        .windows(b"moz_bookmarks".len())
+                        .any(|window| window == b"moz_bookmarks")
+            }
+            Self::Chrome => json_object(data)
+                .is_some_and(|root| root.contains_key("checksum") && root.contains_key("roots")),
+            Self::Html => {
+                let head = String::from_utf8_lossy(&data[..data.len().min(1024)]);
+                head.trim_start_matches('\u{feff}')
+                    .trim_start()
+                    .to_uppercase()
+                    .starts_with("<!DOCTYPE NETSCAPE-BOOKMARK-FILE-1>")
+            }
+            Self::Safari => {
+                (data.starts_with(b"bplist00") || data.starts_with(b"<?xml"))
+                    && data
+                        .windows(b"WebBookmarkTypeList".len())
+                        .any(|window| window == b"WebBookmarkTypeList")
+            }
+            Self::Pinboard => first_json_item(data)
+                .is_some_and(|item| item.contains_key("href") && item.contains_key("time")),
+            Self::Raindrop => csv_header(data).is_some_and(|header| {
+                ["url", "folder", "excerpt"]
+                    .iter()
+                    .all(|column| header.iter().any(|name| name == column))
+            }),
+            Self::Pocket => csv_header(data).is_some_and(|header| {
+                ["url", "time_added"]
+                    .iter()
+                    .all(|column| header.iter().any(|name| name == column))
+            }),
+            Self::Buku => {
+                data.starts_with(b"SQLite format 3\0")
+                    && data
+                        .windows(b"bookmarks (".len())
+                        .any(|window| window.eq_ignore_ascii_case(b"bookmarks ("))
+                    && data
+                        .windows(b"metadata text".len())
+                        .any(|window| window.eq_ignore_ascii_case(b"metadata text"))
+            }
+            Self::Qutebrowser => is_qutebrowser_file(&String::from_utf8_lossy(data), false),
+            Self::Quickmarks => is_qutebrowser_file(&String::from_utf8_lossy(data), true),
+            Self::W3m => String::from_utf8_lossy(data).contains(W3M_SECTION_END),
+            Self::Lynx => String::from_utf8_lossy(data)
+                .to_lowercase()
+                .contains(&LYNX_TITLE.to_lowercase()),
+            Self::Linkding => first_json_item(data)
+                .is_some_and(|item| item.contains_key("url") && item.contains_key("date_added")),
+        }
+    }
+}
+
+fn json_object(data: &[u8]) -> Option<Map<String, Value>> {
+    serde_json::from_slice(data).ok()
+}
+
+/// The first object of a JSON array, or of the `results` array of an API page
+fn first_json_item(data: &[u8]) -> Option<Map<String, Value>> {
+    let value = serde_json::from_slice::<Value>(data).ok()?;
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(mut page) => match page.remove("results") {
+            Some(Value::Array(items)) => items,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    match items.into_iter().next() {
+        Some(Value::Object(item)) => Some(item),
+        _ => None,
+    }
+}
+
+/// The column names of a CSV file, if its first line looks like a CSV header
+fn csv_header(data: &[u8]) -> Option<Vec<String>> {
+    let first_line = data.split(|&byte| byte == b'\n').next()?;
+    let header = csv::Reader::from_reader(first_line).headers().ok()?.clone();
+    Some(header.iter().map(|name| name.trim().to_string()).collect())
+}
+
+/// How a bookmarks file should be read
+#[derive(Debug, Default, Clone)]
+pub struct LoadOptions {
+    /// Skips the format detection
+    pub format: Option<BookmarkFormat>,
+    /// Skips and reports nodes that can't be converted instead of failing the whole import
+    pub lenient: bool,
+    /// Files bookmarks from formats without folders (e.g. buku) under their first tag
+    pub first_tag_folders: bool,
+    /// How each link's canonical URL is worked out
+    pub canonical: CanonicalOptions,
+}
+
+/// Reads a bookmarks export from disk and converts it into a unified `Bookmarks` collection, with
+/// the canonical URL of every link filled in
+///
+/// The format is detected from the file's contents unless `opts.format` is given. mozLz4-compressed
+/// files are decompressed first, and a Firefox `bookmarkbackups` directory resolves to its newest
+/// backup.
+pub fn load_bookmarks(path: &Path, opts: &LoadOptions) -> Result<Bookmarks> {
+    let backup;
+    let path = if path.is_dir() {
+        backup = newest_backup(path)?;
+        backup.as_path()
+    } else {
+        path
+    };
+    let mut data = fs::read(path)
+        .with_context(|| format!("Failed to read bookmarks from {}", path.display()))?;
+    if is_mozlz4(&data) {
+        data = decompress_mozlz4(&data)
+            .with_context(|| format!("Failed to decompress {}", path.display()))?;
+    }
+
+    let Some(format) = opts.format.or_else(|| BookmarkFormat::detect(&data)) else {
+        let tried = BookmarkFormat::value_variants()
+            .iter()
+            .map(|format| format!("\n  - {}", format.signature()))
+            .collect::<String>();
+        bail!(
+            "Could not detect the format of {}. Tried:{tried}\nUse --format to pick one explicitly.",
+            path.display()
+        );
+    };
+
+    let mut converter = Converter::new(opts.lenient);
+    // Stands in for the dates of formats that don't record any
+    let modified = || -> Result<Timestamp> { Ok(fs::metadata(path)?.modified()?.try_into()?) };
+    let mut load = || -> Result<Bookmarks> {
+        let roots = match format {
+            BookmarkFormat::Firefox => {
+                let bookmarks = bookmarks_from_ff_json(std::str::from_utf8(&data)?)?;
+                return Ok(converter.firefox(bookmarks)?);
+            }
+            BookmarkFormat::Places => return Ok(converter.firefox(bookmarks_from_places(path)?)?),
+            BookmarkFormat::Chrome => {
+                let local = read_chrome(path, &data)?;
+                // Signed-in profiles keep their account bookmarks in a file of their own
+                let account_path = path.with_file_name("AccountBookmarks");
+                let account = if path.file_name().is_some_and(|name| name == "Bookmarks")
+                    && account_path.is_file()
+                {
+                    Some(read_chrome(&account_path, &fs::read(&account_path)?)?)
+                } else {
+                    None
+                };
+                return Ok(converter.chrome_with_account(local, account)?);
+            }
+            BookmarkFormat::Html => bookmarks_from_html(&String::from_utf8_lossy(&data))?,
+            BookmarkFormat::Safari => bookmarks_from_safari_plist(&data)?,
+            BookmarkFormat::Pinboard => bookmarks_from_pinboard_json(std::str::from_utf8(&data)?)?,
+            BookmarkFormat::Raindrop => bookmarks_from_raindrop_csv(&data)?,
+            BookmarkFormat::Pocket => bookmarks_from_pocket_csv(&data)?,
+            BookmarkFormat::Linkding => bookmarks_from_linkding_json(std::str::from_utf8(&data)?)?,
+            BookmarkFormat::Buku => bookmarks_from_buku(path, opts.first_tag_folders)?,
+            BookmarkFormat::Qutebrowser => {
+                bookmarks_from_qutebrowser_urls(std::str::from_utf8(&data)?, modified()?)
+            }
+            BookmarkFormat::Quickmarks => {
+                bookmarks_from_qutebrowser_quickmarks(std::str::from_utf8(&data)?, modified()?)
+            }
+            BookmarkFormat::W3m | BookmarkFormat::Lynx => {
+                bookmarks_from_w3m_html(&String::from_utf8_lossy(&data), modified()?)
+            }
+        };
+        Ok(Bookmarks::new(roots, BookmarkSource::default()))
+    };
+    let mut bookmarks = load().with_context(|| {
+        format!(
+            "Failed to load {} as {}",
+            path.display(),
+            format.to_possible_value().unwrap().get_name()
+        )
+    })?;
+    for skipped in &converter.skipped {
+        eprintln!("Skipped {skipped}");
+    }
+    bookmarks.canonicalize(&opts.canonical);
+    for source in &mut bookmarks.sources {
+        source.format = Some(format);
+        source.path = Some(path.to_path_buf());
+        source.exported = modified().ok();
+    }
+    Ok(bookmarks)
+}
+
+/// Parses a Chrom(e|ium) bookmarks file, warning when its checksum is off
+fn read_chrome(path: &Path, data: &[u8]) -> Result<ChromeBookmarks> {
+    let bookmarks = bookmarks_from_chrome_json(std::str::from_utf8(data)?)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    if !bookmarks.checksum_matches() {
+        eprintln!(
+            "Warning: the checksum of {} doesn't match its bookmarks, it may have been read while \
+             the browser was saving it",
+            path.display()
+        );
+    }
+    Ok(bookmarks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::{BookmarkNode, STORAGE};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn detect_format_works() {
+        let firefox = include_bytes!("../../example-firefox.json");
+        let chrome = include_bytes!("../../example-chromium.json");
+        let html = include_bytes!("../../simple-bookmarks.html");
+        assert_eq!(
+            Some(BookmarkFormat::Firefox),
+            BookmarkFormat::detect(firefox)
+        );
+        assert_eq!(Some(BookmarkFormat::Chrome), BookmarkFormat::detect(chrome));
+        assert_eq!(Some(BookmarkFormat::Html), BookmarkFormat::detect(html));
+        for safari in [
+            &include_bytes!("../../example-safari.plist")[..],
+            &include_bytes!("../../example-safari-binary.plist")[..],
+        ] {
+            assert_eq!(Some(BookmarkFormat::Safari), BookmarkFormat::detect(safari));
+        }
+        let places = b"SQLite format 3\0...CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY)";
+        assert_eq!(Some(BookmarkFormat::Places), BookmarkFormat::detect(places));
+        let detect = |data: &str| BookmarkFormat::detect(data.as_bytes());
+        assert_eq!(
+            Some(BookmarkFormat::Pinboard),
+            detect(r#"[{"href": "https://test.website/", "time": "2025-02-26T20:18:25Z"}]"#)
+        );
+        assert_eq!(
+            Some(BookmarkFormat::Linkding),
+            detect(r#"{"results": [{"url": "x", "date_added": "2025-02-26T20:18:25Z"}]}"#)
+        );
+        assert_eq!(
+            Some(BookmarkFormat::Raindrop),
+            detect("id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite\n")
+        );
+        assert_eq!(
+            Some(BookmarkFormat::Pocket),
+            detect("title,url,time_added,tags,status\n")
+        );
+        assert_eq!(
+            Some(BookmarkFormat::Qutebrowser),
+            detect("https://test.website/ Test\n")
+        );
+        assert_eq!(
+            Some(BookmarkFormat::Quickmarks),
+            detect("test https://test.website/\n")
+        );
+        assert_eq!(
+            Some(BookmarkFormat::W3m),
+            detect("<ul>\n<!--End of section (do not delete this comment)-->\n</ul>")
+        );
+        assert_eq!(
+            Some(BookmarkFormat::Lynx),
+            detect("<head>\n<TITLE>Bookmark file</TITLE>\n</head>")
+        );
+        assert_eq!(None, BookmarkFormat::detect(b"{\"foo\": []}"));
+        assert_eq!(None, BookmarkFormat::detect(b"not bookmarks"));
+    }
+
+    #[test]
+    fn load_bookmarks_reports_unknown_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.txt");
+        fs::write(&path, "not bookmarks").unwrap();
+
+        let err = load_bookmarks(&path, &LoadOptions::default())
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Could not detect the format of"));
+        for format in BookmarkFormat::value_variants() {
+            assert!(err.contains(format.signature()));
+        }
+    }
+
+    #[test]
+    fn load_bookmarks_reads_newest_compressed_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = include_bytes!("../../example-firefox.json");
+        let mut compressed = b"mozLz40\0".to_vec();
+        compressed.extend((json.len() as u32).to_le_bytes());
+        compressed.extend(lz4_flex::block::compress(json));
+        fs::write(
+            dir.path().join("bookmarks-2025-01-01_0_old.jsonlz4"),
+            "garbage",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("bookmarks-2025-02-26_1_new.jsonlz4"),
+            compressed,
+        )
+        .unwrap();
+
+        let mut expected: Bookmarks = bookmarks_from_ff_json(std::str::from_utf8(json).unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        expected.canonicalize(&CanonicalOptions::default());
+        let loaded = load_bookmarks(dir.path(), &LoadOptions::default()).unwrap();
+        assert_eq!(expected.roots, loaded.roots);
+        let source = &loaded.sources[0];
+        assert_eq!(Some(BookmarkFormat::Firefox), source.format);
+        assert_eq!(
+            Some(dir.path().join("bookmarks-2025-02-26_1_new.jsonlz4")),
+            source.path
+        );
+        assert!(source.exported.is_some());
+    }
+
+    #[test]
+    fn load_bookmarks_merges_chrome_account_bookmarks() {
+        let dir = tempfile::tempdir().unwrap();
+        // Written back out so its checksum is valid, unlike the fixture's
+        let local = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/example-chromium.json"
+        ))
+        .unwrap();
+        let local = chrome::bookmarks_to_chrome_json(&bookmarks_from_chrome_json(&local).unwrap());
+        fs::write(dir.path().join("Bookmarks"), local.unwrap()).unwrap();
+        fs::write(
+            dir.path().join("AccountBookmarks"),
+            r#"{
+                "checksum": "908f9b26ecf14b6cdf3f9a8e857dc6b5",
+                "roots": {
+                    "bookmark_bar": {
+                        "children": [{
+                            "date_added": "13385507767527863",
+                            "id": "7",
+                            "name": "Account",
+                            "type": "url",
+                            "url": "http://account.website/"
+                        }],
+                        "date_added": "13385507687119717",
+                        "date_modified": "0",
+                        "id": "4",
+                        "name": "Bookmarks bar",
+                        "type": "folder"
+                    }
+                },
+                "version": 1
+            }"#,
+        )
+        .unwrap();
+
+        let bookmarks =
+            load_bookmarks(&dir.path().join("Bookmarks"), &LoadOptions::default()).unwrap();
+        let summary = bookmarks
+            .roots
+            .iter()
+            .map(|root| {
+                let BookmarkNode::Folder {
+                    title,
+                    children,
+                    meta,
+                    ..
+                } = root
+                else {
+                    panic!("Expected the roots to be folders");
+                };
+                (
+                    title.as_str(),
+                    children.len(),
+                    meta.annotations[STORAGE].as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("Bookmarks bar", 1, "local, account"),
+                ("Other bookmarks", 1, "local"),
+                ("Mobile bookmarks", 0, "local"),
+            ],
+            summary
+        );
+        let BookmarkNode::Folder { children, .. } = &bookmarks.roots[0] else {
+            unreachable!();
+        };
+        let BookmarkNode::Link { title, meta, .. } = &children[0] else {
+            panic!("Expected the account bookmark");
+        };
+        assert_eq!(
+            ("Account", "account"),
+            (title.as_str(), meta.annotations[STORAGE].as_str())
+        );
+    }
+
+    #[test]
+    fn load_bookmarks_respects_format_override() {
+        let path = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/example-firefox.json"));
+        assert!(load_bookmarks(
+            path,
+            &LoadOptions {
+                format: Some(BookmarkFormat::Firefox),
+                ..Default::default()
+            },
+        )
+        .is_ok());
+        let err = load_bookmarks(
+            path,
+            &LoadOptions {
+                format: Some(BookmarkFormat::Chrome),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("as chrome"));
+    }
+}
@@ -1,13 +1,179 @@
-use jiff::{civil::Date, Timestamp};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use clap::ValueEnum;
+use jiff::Timestamp;
+use serde_json::Value;
 
 use crate::bookmarks::{
+    canonical::{canonicalize_url, stable_url, CanonicalOptions},
     chrome::{ChromeBookmarkNode, ChromeBookmarks},
-    firefox::FirefoxBookmarkNode,
+    error::ConversionError,
+    firefox::{FirefoxAnnotation, FirefoxBookmarkNode, DESCRIPTION_ANNO},
+    profiles::Browser,
+    BookmarkFormat,
 };
 
-// TODO: Consider using a Bookmarks wrapper struct to more cleanly unify these bookmark variants
-//          (see ChromeBookmarkRoots).
-/// The unifying bookmark type between Firefox and Chrom(e|ium) variants.
+/// A whole bookmark collection: its top-level folders (or links, for formats without folders)
+/// and where it was read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmarks {
+    pub roots: Vec<BookmarkNode>,
+    /// One per file, or several once collections have been merged
+    pub sources: Vec<BookmarkSource>,
+}
+
+/// Where a bookmark collection came from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookmarkSource {
+    pub format: Option<BookmarkFormat>,
+    /// Set when the collection was found through a browser profile
+    pub browser: Option<Browser>,
+    pub profile: Option<String>,
+    pub path: Option<PathBuf>,
+    /// When the file was written, i.e. its modification time
+    pub exported: Option<Timestamp>,
+    /// The format's own version number, if it has one (e.g. Chrom(e|ium)'s `version`)
+    pub version: Option<String>,
+}
+
+impl BookmarkSource {
+    /// A short human-readable name, e.g. `firefox (default-release)` or the file path
+    pub fn label(&self) -> String {
+        match (&self.browser, &self.profile, &self.path) {
+            (Some(browser), Some(profile), _) => format!("{browser} ({profile})"),
+            (_, _, Some(path)) => path.display().to_string(),
+            (Some(browser), None, None) => browser.to_string(),
+            (None, _, None) => self
+                .format
+                .and_then(|format| format.to_possible_value())
+                .map(|format| format.get_name().to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Bookmarks {
+    pub fn new(roots: Vec<BookmarkNode>, source: BookmarkSource) -> Self {
+        Self {
+            roots,
+            sources: vec![source],
+        }
+    }
+
+    /// Counts the links in every root
+    pub fn link_count(&self) -> usize {
+        self.links().count()
+    }
+
+    /// Renames, hides or collapses the built-in roots so every browser gives the same layout
+    pub fn arrange_roots(&mut self, opts: &RootOptions) {
+        for mut root in std::mem::take(&mut self.roots) {
+            let kind = match &root {
+                BookmarkNode::Folder { meta, .. } => meta.root_kind(),
+                _ => None,
+            };
+            let Some(kind) = kind else {
+                self.roots.push(root);
+                continue;
+            };
+            if opts.hide.contains(&kind) {
+                continue;
+            }
+            if opts.collapse.contains(&kind) {
+                if let BookmarkNode::Folder { children, .. } = root {
+                    merge_children(&mut self.roots, children, &mut |_| {});
+                }
+                continue;
+            }
+            if let BookmarkNode::Folder { title, .. } = &mut root {
+                *title = opts
+                    .rename
+                    .get(&kind)
+                    .cloned()
+                    .unwrap_or_else(|| kind.title().to_string());
+            }
+            self.roots.push(root);
+        }
+    }
+
+    /// Works out the canonical URL of every link
+    pub fn canonicalize(&mut self, opts: &CanonicalOptions) {
+        self.walk_mut(&mut |node, _| {
+            if let BookmarkNode::Link { url, meta, .. } = node {
+                meta.canonical_url = Some(canonicalize_url(url, opts));
+            }
+        });
+    }
+
+    /// Records the collection's sources on every link, so they survive a merge
+    pub fn record_provenance(&mut self) {
+        let labels = self
+            .sources
+            .iter()
+            .map(BookmarkSource::label)
+            .collect::<Vec<_>>();
+        self.walk_mut(&mut |node, _| {
+            if let BookmarkNode::Link { meta, .. } = node {
+                meta.sources = labels.clone();
+            }
+        });
+    }
+}
+
+/// The built-in folders every browser has in some form, whatever it calls them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+pub enum RootKind {
+    /// Firefox's Bookmarks Toolbar, Chrome's Bookmarks bar, Safari's Favorites
+    Toolbar,
+    /// Firefox's Bookmarks Menu, Safari's Bookmarks Menu
+    Menu,
+    /// Firefox's Other Bookmarks, Chrome's Other bookmarks
+    Other,
+    /// Firefox's Mobile Bookmarks, Chrome's Mobile bookmarks
+    Mobile,
+    /// Safari's Reading List
+    ReadingList,
+}
+
+impl RootKind {
+    /// Maps a source's own name for a built-in folder (see [`BookmarkMeta::root`])
+    pub fn from_source(root: &str) -> Option<Self> {
+        match root {
+            "toolbarFolder" | "bookmark_bar" | "BookmarksBar" | "personal_toolbar_folder" => {
+                Some(Self::Toolbar)
+            }
+            "bookmarksMenuFolder" | "BookmarksMenu" => Some(Self::Menu),
+            "unfiledBookmarksFolder" | "other" | "unfiled_bookmarks_folder" => Some(Self::Other),
+            "mobileFolder" | "synced" => Some(Self::Mobile),
+            "com.apple.ReadingList" => Some(Self::ReadingList),
+            _ => None,
+        }
+    }
+
+    /// The folder name used in the vault unless the root is renamed
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Toolbar => "Bookmarks Toolbar",
+            Self::Menu => "Bookmarks Menu",
+            Self::Other => "Other Bookmarks",
+            Self::Mobile => "Mobile Bookmarks",
+            Self::ReadingList => "Reading List",
+        }
+    }
+}
+
+/// How the built-in roots are laid out in the vault
+#[derive(Debug, Clone, Default)]
+pub struct RootOptions {
+    /// Folder names to use instead of [`RootKind::title`]
+    pub rename: BTreeMap<RootKind, String>,
+    /// Roots left out of the vault along with everything in them
+    pub hide: Vec<RootKind>,
+    /// Roots whose contents go straight into the vault instead of a folder of their own
+    pub collapse: Vec<RootKind>,
+}
+
+/// The unifying bookmark type between all of the supported sources
 #[derive(Debug, Clone, PartialEq)]
 pub enum BookmarkNode {
     Link {
@@ -16,122 +182,568 @@ pub enum BookmarkNode {
         add_date: Timestamp,
         last_modified: Timestamp,
         tags: Option<Vec<String>>,
+        meta: BookmarkMeta,
     },
     Folder {
         title: String,
         add_date: Timestamp,
         last_modified: Timestamp,
         children: Vec<BookmarkNode>,
+        meta: BookmarkMeta,
+    },
+    Separator {
+        add_date: Timestamp,
+        last_modified: Timestamp,
+        meta: BookmarkMeta,
     },
 }
 
-impl From<ChromeBookmarks> for BookmarkNode {
-    fn from(value: ChromeBookmarks) -> Self {
-        let roots = vec![
-            value.roots.bookmark_bar,
-            value.roots.other,
-            value.roots.synced,
-        ];
+/// Everything else a source knows about a node, kept so that none of it is lost on the way to
+/// the vault
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookmarkMeta {
+    /// The source's globally unique ID for the node
+    pub guid: Option<String>,
+    /// The source's own (per-profile) ID for the node
+    pub id: Option<String>,
+    /// Position within the parent folder
+    pub index: Option<u32>,
+    pub description: Option<String>,
+    /// Shortcut that opens the bookmark from the address bar
+    pub keyword: Option<String>,
+    /// POST body sent when the bookmark is opened through its keyword
+    pub post_data: Option<String>,
+    pub icon_uri: Option<String>,
+    /// Which built-in folder this is, as named by the source (e.g. `toolbarFolder`)
+    pub root: Option<String>,
+    /// When the bookmark was last opened, if the source tracks it
+    pub last_used: Option<Timestamp>,
+    /// The browser profiles or files the bookmark was imported from
+    pub sources: Vec<String>,
+    /// The link's URL in canonical form, kept alongside the original (see [`canonicalize_url`])
+    pub canonical_url: Option<String>,
+    /// Every folder a merged duplicate was found in (see [`dedupe`](super::dedupe::dedupe))
+    pub folders: Vec<String>,
+    /// Remaining source-specific key/value pairs (Firefox annotations, Chrome `meta_info`, ...)
+    pub annotations: BTreeMap<String, String>,
+}
 
-        BookmarkNode::Folder {
-            title: "Root".to_string(),
-            add_date: chrome_to_unix_timestamp("0".to_string()),
-            last_modified: chrome_to_unix_timestamp("0".to_string()),
-            children: roots
-                .into_iter()
-                .map(|root| match root {
-                    ChromeBookmarkNode::Folder {
-                        name,
-                        date_added,
-                        date_modified,
-                        children,
-                    } => BookmarkNode::Folder {
-                        title: name,
-                        add_date: chrome_to_unix_timestamp(date_added),
-                        last_modified: chrome_to_unix_timestamp(date_modified),
-                        children: children.into_iter().map(|child| child.into()).collect(),
-                    },
-                    _ => unreachable!(),
-                })
-                .collect(),
+impl BookmarkMeta {
+    /// Which canonical root this is, if it's one of the built-in folders
+    pub fn root_kind(&self) -> Option<RootKind> {
+        self.root.as_deref().and_then(RootKind::from_source)
+    }
+
+    /// The canonical form of the link's URL, worked out with the default options if that wasn't
+    /// done when it was loaded
+    pub fn canonical_url_of(&self, url: &str) -> String {
+        self.canonical_url
+            .clone()
+            .unwrap_or_else(|| canonicalize_url(url, &CanonicalOptions::default()))
+    }
+
+    /// An ID for the link that survives renames and moves: the source's guid, or else a hash of
+    /// its [`stable_url`]
+    pub fn bookmark_id(&self, url: &str) -> String {
+        match &self.guid {
+            Some(guid) => guid.clone(),
+            None => format!("{:x}", md5::compute(stable_url(url))),
         }
     }
 }
 
-impl From<ChromeBookmarkNode> for BookmarkNode {
-    fn from(value: ChromeBookmarkNode) -> Self {
+impl BookmarkNode {
+    pub fn meta_mut(&mut self) -> &mut BookmarkMeta {
+        match self {
+            BookmarkNode::Link { meta, .. }
+            | BookmarkNode::Folder { meta, .. }
+            | BookmarkNode::Separator { meta, .. } => meta,
+        }
+    }
+
+    /// Sets an annotation on this node and all of its descendants
+    pub fn annotate_all(&mut self, key: &str, value: &str) {
+        self.walk_mut(&mut |node, _| {
+            node.meta_mut()
+                .annotations
+                .insert(key.to_string(), value.to_string());
+        });
+    }
+}
+
+/// Moves `from` into `into`, unifying folders that are the same built-in root (even from different
+/// browsers) or have the same title at the same level rather than duplicating them
+///
+/// `on_unify` is called with each folder that absorbs another one.
+pub fn merge_children(
+    into: &mut Vec<BookmarkNode>,
+    from: Vec<BookmarkNode>,
+    on_unify: &mut impl FnMut(&mut BookmarkNode),
+) {
+    for node in from {
+        let existing = match &node {
+            BookmarkNode::Folder { title, meta, .. } => into.iter().position(|existing| {
+                matches!(
+                    existing,
+                    BookmarkNode::Folder { title: existing_title, meta: existing_meta, .. }
+                        if match (&meta.root, &existing_meta.root) {
+                            (Some(root), Some(existing_root)) => {
+                                root == existing_root
+                                    || meta.root_kind().is_some()
+                                        && meta.root_kind() == existing_meta.root_kind()
+                            }
+                            _ => title == existing_title,
+                        }
+                )
+            }),
+            _ => None,
+        };
+        match existing {
+            Some(position) => {
+                let BookmarkNode::Folder { children: from, .. } = node else {
+                    unreachable!("only folders are unified");
+                };
+                let folder = &mut into[position];
+                if let BookmarkNode::Folder { children, .. } = folder {
+                    merge_children(children, from, on_unify);
+                }
+                on_unify(folder);
+            }
+            None => into.push(node),
+        }
+    }
+}
+
+/// Converts browser bookmark trees into `BookmarkNode`s, either failing on the first bad node or,
+/// when lenient, skipping it and recording why
+#[derive(Debug)]
+pub struct Converter {
+    pub lenient: bool,
+    pub skipped: Vec<ConversionError>,
+}
+
+impl Converter {
+    pub fn new(lenient: bool) -> Self {
+        Self {
+            lenient,
+            skipped: vec![],
+        }
+    }
+
+    /// Converts a whole Chrom(e|ium) `Bookmarks` file
+    ///
+    /// Missing roots are left out, and unknown roots are converted after the known ones. An
+    /// unknown root that isn't a valid node is an error like any other bad node.
+    pub fn chrome(&mut self, value: ChromeBookmarks) -> Result<Bookmarks, ConversionError> {
+        let source = BookmarkSource {
+            version: Some(value.version.to_string()),
+            ..Default::default()
+        };
+        let mut roots = vec![
+            (
+                "roots.bookmark_bar".to_string(),
+                Ok(value.roots.bookmark_bar),
+            ),
+            ("roots.other".to_string(), Ok(value.roots.other)),
+            ("roots.synced".to_string(), Ok(value.roots.synced)),
+        ];
+        // Values that aren't objects, such as `sync_transaction_version`, aren't roots at all
+        roots.extend(
+            value
+                .roots
+                .extra
+                .into_iter()
+                .filter(|(_, root)| root.is_object())
+                .map(|(key, root)| {
+                    let path = format!("roots.{key}");
+                    let root = serde_json::from_value::<ChromeBookmarkNode>(root).map_err(|err| {
+                        ConversionError::InvalidNode {
+                            path: path.clone(),
+                            message: err.to_string(),
+                        }
+                    });
+                    (path, root.map(Some))
+                }),
+        );
+        let mut children = vec![];
+        for (index, (path, root)) in roots.into_iter().enumerate() {
+            let root = match root {
+                Ok(Some(root @ ChromeBookmarkNode::Folder { .. })) => {
+                    let name = path.trim_start_matches("roots.").to_string();
+                    self.chrome_node(root, index as u32, path).map(|mut root| {
+                        if let BookmarkNode::Folder { meta, .. } = &mut root {
+                            meta.root = Some(name);
+                        }
+                        root
+                    })
+                }
+                Ok(Some(ChromeBookmarkNode::Url { .. })) => {
+                    Err(ConversionError::UrlAtRoot { path })
+                }
+                Ok(None) => continue,
+                Err(err) => Err(err),
+            };
+            children.extend(self.skip_if_lenient(root)?);
+        }
+        Ok(Bookmarks::new(children, source))
+    }
+
+    /// Converts a Chrom(e|ium) `Bookmarks` file along with its `AccountBookmarks` file, if any
+    ///
+    /// Every node gets a `storage` annotation saying which of the two it came from, and folders
+    /// present in both (e.g. the bookmark bar) are unified with a `storage` of "local, account".
+    pub fn chrome_with_account(
+        &mut self,
+        local: ChromeBookmarks,
+        account: Option<ChromeBookmarks>,
+    ) -> Result<Bookmarks, ConversionError> {
+        let mut bookmarks = self.chrome(local)?;
+        let Some(account) = account else {
+            return Ok(bookmarks);
+        };
+        let mut account = self.chrome(account)?;
+        for (roots, storage) in [
+            (&mut bookmarks.roots, "local"),
+            (&mut account.roots, "account"),
+        ] {
+            for root in roots {
+                root.annotate_all(STORAGE, storage);
+            }
+        }
+        merge_children(&mut bookmarks.roots, account.roots, &mut |folder| {
+            folder
+                .meta_mut()
+                .annotations
+                .insert(STORAGE.to_string(), "local, account".to_string());
+        });
+        Ok(bookmarks)
+    }
+
+    /// Converts a Firefox JSON backup, whose root place container holds the built-in folders
+    pub fn firefox(&mut self, value: FirefoxBookmarkNode) -> Result<Bookmarks, ConversionError> {
+        let roots = match self.firefox_node(value, String::new())? {
+            BookmarkNode::Folder { children, .. } => children,
+            node => vec![node],
+        };
+        Ok(Bookmarks::new(roots, BookmarkSource::default()))
+    }
+
+    fn chrome_node(
+        &mut self,
+        value: ChromeBookmarkNode,
+        index: u32,
+        path: String,
+    ) -> Result<BookmarkNode, ConversionError> {
+        // Chrome writes "0" (or nothing) for dates it never recorded, which are then left at the Unix
+        // epoch like every other source does
+        let date = |field: &'static str, value: &str| match value {
+            "" | "0" => Ok(None),
+            _ => chrome_to_unix_timestamp(value)
+                .map(Some)
+                .ok_or_else(|| invalid_timestamp(&path, field, value)),
+        };
+        let meta =
+            |guid, id, last_used, meta_info: Option<BTreeMap<String, String>>| BookmarkMeta {
+                guid,
+                id,
+                index: Some(index),
+                last_used,
+                annotations: meta_info.unwrap_or_default(),
+                ..Default::default()
+            };
+
         match value {
             ChromeBookmarkNode::Folder {
+                guid,
+                id,
                 name,
                 date_added,
+                date_last_used,
                 date_modified,
+                meta_info,
                 children,
-            } => BookmarkNode::Folder {
-                title: name,
-                add_date: chrome_to_unix_timestamp(date_added),
-                last_modified: chrome_to_unix_timestamp(date_modified),
-                children: children.into_iter().map(|child| child.into()).collect(),
-            },
+            } => {
+                let add_date = date("date_added", &date_added)?.unwrap_or(Timestamp::UNIX_EPOCH);
+                let last_modified = date("date_modified", &date_modified)?.unwrap_or(add_date);
+                let last_used = date("date_last_used", date_last_used.as_deref().unwrap_or(""))?;
+                let meta = meta(guid, id, last_used, meta_info);
+                let mut converted = vec![];
+                for (index, child) in children.into_iter().enumerate() {
+                    let child =
+                        self.chrome_node(child, index as u32, format!("{path}.children[{index}]"));
+                    converted.extend(self.skip_if_lenient(child)?);
+                }
+                Ok(BookmarkNode::Folder {
+                    title: name,
+                    add_date,
+                    last_modified,
+                    children: converted,
+                    meta,
+                })
+            }
             ChromeBookmarkNode::Url {
+                guid,
+                id,
                 name,
                 date_added,
                 date_last_used,
+                meta_info,
                 url,
-            } => BookmarkNode::Link {
-                title: name,
-                url,
-                add_date: chrome_to_unix_timestamp(date_added),
-                last_modified: chrome_to_unix_timestamp(date_last_used),
-                tags: None,
-            },
+            } => {
+                let add_date = date("date_added", &date_added)?.unwrap_or(Timestamp::UNIX_EPOCH);
+                let last_used = date("date_last_used", date_last_used.as_deref().unwrap_or(""))?;
+                Ok(BookmarkNode::Link {
+                    title: name,
+                    url,
+                    add_date,
+                    // Chrome doesn't track edits to URLs
+                    last_modified: add_date,
+                    tags: None,
+                    meta: meta(guid, id, last_used, meta_info),
+                })
+            }
         }
     }
-}
 
-impl From<FirefoxBookmarkNode> for BookmarkNode {
-    fn from(value: FirefoxBookmarkNode) -> Self {
+    fn firefox_node(
+        &mut self,
+        value: FirefoxBookmarkNode,
+        path: String,
+    ) -> Result<BookmarkNode, ConversionError> {
+        let dates = |date_added: u64, last_modified: u64| {
+            Ok((
+                firefox_to_timestamp(date_added)
+                    .ok_or_else(|| invalid_timestamp(&path, "dateAdded", date_added))?,
+                firefox_to_timestamp(last_modified)
+                    .ok_or_else(|| invalid_timestamp(&path, "lastModified", last_modified))?,
+            ))
+        };
         match value {
             FirefoxBookmarkNode::PlaceContainer {
+                guid,
                 title,
+                index,
                 date_added,
                 last_modified,
+                id,
+                root,
+                annos,
                 children,
-            } => BookmarkNode::Folder {
-                title,
-                add_date: Timestamp::from_microsecond(date_added.try_into().unwrap()).unwrap(),
-                last_modified: Timestamp::from_microsecond(last_modified.try_into().unwrap())
-                    .unwrap(),
-                children: children
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|child| child.into())
-                    .collect(),
-            },
+            } => {
+                let (add_date, last_modified) = dates(date_added, last_modified)?;
+                let mut converted = vec![];
+                for (index, child) in children.unwrap_or_default().into_iter().enumerate() {
+                    let child_path = match path.as_str() {
+                        "" => format!("children[{index}]"),
+                        path => format!("{path}.children[{index}]"),
+                    };
+                    let child = self.firefox_node(child, child_path);
+                    converted.extend(self.skip_if_lenient(child)?);
+                }
+                Ok(BookmarkNode::Folder {
+                    title,
+                    add_date,
+                    last_modified,
+                    children: converted,
+                    meta: BookmarkMeta {
+                        root,
+                        ..firefox_meta(guid, id, index, annos)
+                    },
+                })
+            }
             FirefoxBookmarkNode::Place {
+                guid,
                 title,
+                index,
                 date_added,
                 last_modified,
+                id,
                 tags,
                 uri,
-            } => BookmarkNode::Link {
-                title,
-                url: uri,
-                add_date: Timestamp::from_microsecond(date_added.try_into().unwrap()).unwrap(),
-                last_modified: Timestamp::from_microsecond(last_modified.try_into().unwrap())
-                    .unwrap(),
-                tags: Some(tags.split(',').map(|tag| tag.to_string()).collect()),
-            },
+                keyword,
+                post_data,
+                icon_uri,
+                annos,
+            } => {
+                let (add_date, last_modified) = dates(date_added, last_modified)?;
+                Ok(BookmarkNode::Link {
+                    title,
+                    url: uri,
+                    add_date,
+                    last_modified,
+                    tags: tags.and_then(|tags| split_tags(&tags, |c| c == ',')),
+                    meta: BookmarkMeta {
+                        keyword,
+                        post_data,
+                        icon_uri,
+                        ..firefox_meta(guid, id, index, annos)
+                    },
+                })
+            }
+            FirefoxBookmarkNode::Separator {
+                guid,
+                index,
+                date_added,
+                last_modified,
+                id,
+                annos,
+                ..
+            } => {
+                let (add_date, last_modified) = dates(date_added, last_modified)?;
+                Ok(BookmarkNode::Separator {
+                    add_date,
+                    last_modified,
+                    meta: firefox_meta(guid, id, index, annos),
+                })
+            }
+        }
+    }
+
+    /// Turns a failed child conversion into a skipped one when lenient
+    fn skip_if_lenient(
+        &mut self,
+        result: Result<BookmarkNode, ConversionError>,
+    ) -> Result<Option<BookmarkNode>, ConversionError> {
+        match result {
+            Ok(node) => Ok(Some(node)),
+            Err(err) if self.lenient => {
+                self.skipped.push(err);
+                Ok(None)
+            }
+            Err(err) => Err(err),
         }
     }
 }
 
-/// Converts a chrome-flavored timestamp string (epoch 1601-01-01) and returns it as a Unix-flavored jiff Timestamp.
-fn chrome_to_unix_timestamp(chrome_string: String) -> Timestamp {
-    let chrome_timestamp =
-        Timestamp::from_microsecond(chrome_string.parse::<i64>().unwrap()).unwrap();
-    let chrome_epoch: Timestamp = Date::constant(1601, 1, 1).in_tz("UTC").unwrap().into();
-    let delta = chrome_timestamp.as_duration();
-    chrome_epoch.checked_add(delta).unwrap()
+impl TryFrom<ChromeBookmarks> for Bookmarks {
+    type Error = ConversionError;
+
+    fn try_from(value: ChromeBookmarks) -> Result<Self, Self::Error> {
+        Converter::new(false).chrome(value)
+    }
+}
+
+impl TryFrom<FirefoxBookmarkNode> for Bookmarks {
+    type Error = ConversionError;
+
+    fn try_from(value: FirefoxBookmarkNode) -> Result<Self, Self::Error> {
+        Converter::new(false).firefox(value)
+    }
+}
+
+/// The fields every Firefox node has, with the description pulled out of its annotations
+fn firefox_meta(
+    guid: String,
+    id: u64,
+    index: u32,
+    annos: Option<Vec<FirefoxAnnotation>>,
+) -> BookmarkMeta {
+    let mut annotations = annos
+        .unwrap_or_default()
+        .into_iter()
+        .map(|anno| {
+            let value = match anno.value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+            (anno.name, value)
+        })
+        .collect::<BTreeMap<_, _>>();
+    BookmarkMeta {
+        guid: Some(guid),
+        id: Some(id.to_string()),
+        index: Some(index),
+        description: annotations
+            .remove(DESCRIPTION_ANNO)
+            .filter(|description| !description.is_empty()),
+        annotations,
+        ..Default::default()
+    }
+}
+
+/// Merges several collections into the first one, unifying folders at the same path
+///
+/// Links saved in more than one place are all kept; see [`dedupe`](super::dedupe::dedupe) for
+/// collapsing them.
+pub fn merge_trees(collections: Vec<Bookmarks>) -> Option<Bookmarks> {
+    let mut collections = collections.into_iter();
+    let mut merged = collections.next()?;
+    for collection in collections {
+        merge_children(&mut merged.roots, collection.roots, &mut |_| {});
+        merged.sources.extend(collection.sources);
+    }
+    Some(merged)
+}
+
+/// Finds the children of the folder at `path` below `children`, creating any folders that are
+/// missing along the way
+pub fn folder_by_path<'a>(
+    mut children: &'a mut Vec<BookmarkNode>,
+    path: &[&str],
+) -> &'a mut Vec<BookmarkNode> {
+    for &name in path {
+        let position = children
+            .iter()
+            .position(|child| matches!(child, BookmarkNode::Folder { title, .. } if title == name));
+        let position = position.unwrap_or_else(|| {
+            children.push(BookmarkNode::Folder {
+                title: name.to_string(),
+                add_date: Timestamp::UNIX_EPOCH,
+                last_modified: Timestamp::UNIX_EPOCH,
+                children: vec![],
+                meta: BookmarkMeta {
+                    index: Some(children.len() as u32),
+                    ..Default::default()
+                },
+            });
+            children.len() - 1
+        });
+        let BookmarkNode::Folder {
+            children: nested, ..
+        } = &mut children[position]
+        else {
+            unreachable!("position only matches folders");
+        };
+        children = nested;
+    }
+    children
+}
+
+/// Splits a list of tags, trimming each one and dropping empties, or `None` when none are left
+pub fn split_tags(tags: &str, is_separator: impl Fn(char) -> bool) -> Option<Vec<String>> {
+    let tags = tags
+        .split(is_separator)
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string())
+        .collect::<Vec<_>>();
+    (!tags.is_empty()).then_some(tags)
+}
+
+fn invalid_timestamp(path: &str, field: &'static str, value: impl ToString) -> ConversionError {
+    ConversionError::InvalidTimestamp {
+        path: path.to_string(),
+        field,
+        value: value.to_string(),
+    }
+}
+
+/// The annotation saying whether a Chrom(e|ium) node is a local or an account bookmark
+pub const STORAGE: &str = "storage";
+
+/// 1601-01-01, the epoch of Chrom(e|ium) (and Windows) timestamps
+const CHROME_EPOCH: Timestamp = Timestamp::constant(-11_644_473_600, 0);
+
+/// Converts a chrome-flavored timestamp string (microseconds since 1601-01-01) into a jiff Timestamp
+fn chrome_to_unix_timestamp(chrome_string: &str) -> Option<Timestamp> {
+    let microseconds = chrome_string.parse::<i64>().ok()?;
+    let delta = jiff::SignedDuration::from_micros(microseconds);
+    CHROME_EPOCH.checked_add(delta).ok()
+}
+
+/// Converts a Firefox timestamp (microseconds since the Unix epoch) into a jiff Timestamp
+fn firefox_to_timestamp(microseconds: u64) -> Option<Timestamp> {
+    Timestamp::from_microsecond(microseconds.try_into().ok()?).ok()
 }
 
 #[cfg(test)]
@@ -146,104 +758,564 @@ mod tests {
     #[test]
     fn chrome_to_unix_timestamp_works() {
         let expected: Timestamp = date_to_ts("2025-03-06T02:51:31Z");
-        let input = "13385703091000000".to_string();
+        let input = "13385703091000000";
         let result = chrome_to_unix_timestamp(input);
-        assert_eq!(expected, result);
+        assert_eq!(Some(expected), result);
+        assert_eq!(None, chrome_to_unix_timestamp("yesterday"));
+    }
+
+    #[test]
+    fn bookmark_source_label_works() {
+        let profile = BookmarkSource {
+            browser: Some(Browser::Firefox),
+            profile: Some("default-release".to_string()),
+            path: Some("places.sqlite".into()),
+            ..Default::default()
+        };
+        assert_eq!("firefox (default-release)", profile.label());
+        let file = BookmarkSource {
+            format: Some(BookmarkFormat::Html),
+            path: Some("bookmarks.html".into()),
+            ..Default::default()
+        };
+        assert_eq!("bookmarks.html", file.label());
+        let format = BookmarkSource {
+            format: Some(BookmarkFormat::Html),
+            ..Default::default()
+        };
+        assert_eq!("html", format.label());
+    }
+
+    #[test]
+    fn bookmark_id_prefers_the_guid() {
+        let guid = BookmarkMeta {
+            guid: Some("mAQ-wHKmGwfH".to_string()),
+            ..Default::default()
+        };
+        assert_eq!("mAQ-wHKmGwfH", guid.bookmark_id("https://test.website/"));
+        // Without one, the ID only depends on the canonical URL
+        let meta = BookmarkMeta::default();
+        assert_eq!(
+            meta.bookmark_id("https://test.website/"),
+            meta.bookmark_id("HTTPS://Test.Website/?utm_source=feed#top")
+        );
+        assert_ne!(
+            meta.bookmark_id("https://test.website/"),
+            meta.bookmark_id("https://test.website/other")
+        );
+        // Nor on the options the canonical URL was worked out with
+        let keeps_fragment = BookmarkMeta {
+            canonical_url: Some("https://test.website/#top".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            meta.bookmark_id("https://test.website/#top"),
+            keeps_fragment.bookmark_id("https://test.website/#top")
+        );
+    }
+
+    #[test]
+    fn chrome_conversion_reports_bad_nodes() {
+        let mut input: ChromeBookmarks =
+            serde_json::from_str(include_str!("../../example-chromium.json")).unwrap();
+        let Some(ChromeBookmarkNode::Folder { children, .. }) = &mut input.roots.other else {
+            panic!("Expected the other root to be a folder");
+        };
+        let url = |name: &str, date_added: &str| ChromeBookmarkNode::Url {
+            guid: None,
+            id: None,
+            name: name.to_string(),
+            date_added: date_added.to_string(),
+            date_last_used: None,
+            meta_info: None,
+            url: format!("http://{}.website/", name.to_lowercase()),
+        };
+        children.push(url("Broken", "yesterday"));
+        input.roots.synced = Some(url("Misplaced", "0"));
+        // Only objects are taken for roots, and this one is missing its dates
+        input.roots.extra = BTreeMap::from([
+            ("sync_transaction_version".to_string(), Value::from("4")),
+            (
+                "workspaces".to_string(),
+                serde_json::json!({"type": "folder", "name": "Workspaces"}),
+            ),
+        ]);
+        let expected_errors = vec![
+            ConversionError::InvalidTimestamp {
+                path: "roots.other.children[1]".to_string(),
+                field: "date_added",
+                value: "yesterday".to_string(),
+            },
+            ConversionError::UrlAtRoot {
+                path: "roots.synced".to_string(),
+            },
+            ConversionError::InvalidNode {
+                path: "roots.workspaces".to_string(),
+                message: "missing field `date_added`".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            Err(expected_errors[0].clone()),
+            Bookmarks::try_from(input.clone())
+        );
+        let mut broken_root = input.clone();
+        broken_root.roots.other = None;
+        broken_root.roots.synced = None;
+        assert_eq!(
+            Err(expected_errors[2].clone()),
+            Bookmarks::try_from(broken_root)
+        );
+
+        let mut converter = Converter::new(true);
+        let result = converter.chrome(input).unwrap();
+        assert_eq!(expected_errors, converter.skipped);
+        assert_eq!(1, result.link_count());
+        assert_eq!(
+            "roots.other.children[1]: invalid date_added \"yesterday\"",
+            expected_errors[0].to_string()
+        );
+    }
+
+    #[test]
+    fn firefox_conversion_reports_bad_nodes() {
+        let folder = |title: &str, children| FirefoxBookmarkNode::PlaceContainer {
+            guid: format!("{title:_<12}"),
+            title: title.to_string(),
+            index: 0,
+            date_added: 0,
+            last_modified: 0,
+            id: 1,
+            root: None,
+            annos: None,
+            children: Some(children),
+        };
+        let input = folder(
+            "",
+            vec![folder(
+                "Folder",
+                vec![
+                    FirefoxBookmarkNode::Separator {
+                        guid: "separator___".to_string(),
+                        title: "".to_string(),
+                        index: 0,
+                        date_added: 0,
+                        last_modified: 0,
+                        id: 2,
+                        annos: None,
+                    },
+                    FirefoxBookmarkNode::Place {
+                        guid: "broken______".to_string(),
+                        title: "Broken".to_string(),
+                        index: 1,
+                        date_added: u64::MAX,
+                        last_modified: 0,
+                        id: 3,
+                        tags: None,
+                        uri: "http://broken.website/".to_string(),
+                        keyword: None,
+                        post_data: None,
+                        icon_uri: None,
+                        annos: None,
+                    },
+                ],
+            )],
+        );
+
+        let mut converter = Converter::new(true);
+        converter.firefox(input.clone()).unwrap();
+        assert_eq!(
+            vec![ConversionError::InvalidTimestamp {
+                path: "children[0].children[1]".to_string(),
+                field: "dateAdded",
+                value: u64::MAX.to_string(),
+            }],
+            converter.skipped
+        );
+        assert!(Bookmarks::try_from(input).is_err());
+    }
+
+    #[test]
+    fn firefox_annotations_become_meta() {
+        let input = FirefoxBookmarkNode::Place {
+            guid: "annotated___".to_string(),
+            title: "Annotated".to_string(),
+            index: 2,
+            date_added: 0,
+            last_modified: 0,
+            id: 7,
+            tags: Some("".to_string()),
+            uri: "http://annotated.website/".to_string(),
+            keyword: None,
+            post_data: Some("q=%s".to_string()),
+            icon_uri: Some("http://annotated.website/favicon.ico".to_string()),
+            annos: Some(vec![
+                FirefoxAnnotation {
+                    name: DESCRIPTION_ANNO.to_string(),
+                    value: "A description".into(),
+                    flags: 0,
+                    expires: 4,
+                },
+                FirefoxAnnotation {
+                    name: "bookmarkProperties/loadInSidebar".to_string(),
+                    value: 1.into(),
+                    flags: 0,
+                    expires: 4,
+                },
+            ]),
+        };
+        let expected = BookmarkNode::Link {
+            title: "Annotated".to_string(),
+            url: "http://annotated.website/".to_string(),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            tags: None,
+            meta: BookmarkMeta {
+                guid: Some("annotated___".to_string()),
+                id: Some("7".to_string()),
+                index: Some(2),
+                description: Some("A description".to_string()),
+                post_data: Some("q=%s".to_string()),
+                icon_uri: Some("http://annotated.website/favicon.ico".to_string()),
+                annotations: BTreeMap::from([(
+                    "bookmarkProperties/loadInSidebar".to_string(),
+                    "1".to_string(),
+                )]),
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            Ok(Bookmarks::new(vec![expected], BookmarkSource::default())),
+            Bookmarks::try_from(input)
+        );
     }
 
     #[test]
     fn chrome_conversion_works() {
         let input = include_str!("../../example-chromium.json");
-        let expected = BookmarkNode::Folder {
-            title: "Root".to_string(),
-            add_date: date_to_ts("1601-01-01T00:00:00Z"),
-            last_modified: date_to_ts("1601-01-01T00:00:00Z"),
-            children: vec![
-                BookmarkNode::Folder {
-                    title: "Bookmarks bar".to_string(),
-                    add_date: date_to_ts("2025-03-03T20:34:47.119717Z"),
-                    last_modified: date_to_ts("1601-01-01T00:00:00Z"),
-                    children: vec![],
-                },
-                BookmarkNode::Folder {
-                    title: "Other bookmarks".to_string(),
-                    add_date: date_to_ts("2025-03-03T20:34:47.119721Z"),
-                    last_modified: date_to_ts("2025-03-03T20:36:07.527863Z"),
-                    children: vec![BookmarkNode::Folder {
-                        title: "Test".to_string(),
-                        add_date: date_to_ts("2025-03-03T20:35:09.128841Z"),
-                        last_modified: date_to_ts("2025-03-03T20:36:11.273144Z"),
-                        children: vec![BookmarkNode::Link {
-                            title: "test.website".to_string(),
-                            url: "http://test.website/".to_string(),
-                            add_date: date_to_ts("2025-03-03T20:36:07.527863Z"),
-                            last_modified: date_to_ts("1601-01-01T00:00:00Z"),
-                            tags: None,
-                        }],
+        let meta = |guid: &str, id: &str, index, root: Option<&str>| BookmarkMeta {
+            guid: Some(guid.to_string()),
+            id: Some(id.to_string()),
+            index: Some(index),
+            root: root.map(|root| root.to_string()),
+            ..Default::default()
+        };
+        let expected = vec![
+            BookmarkNode::Folder {
+                title: "Bookmarks bar".to_string(),
+                meta: meta(
+                    "0bc5d13f-2cba-5d74-951f-3f233fe6c908",
+                    "1",
+                    0,
+                    Some("bookmark_bar"),
+                ),
+                add_date: date_to_ts("2025-03-03T20:34:47.119717Z"),
+                last_modified: date_to_ts("2025-03-03T20:34:47.119717Z"),
+                children: vec![],
+            },
+            BookmarkNode::Folder {
+                title: "Other bookmarks".to_string(),
+                meta: meta(
+                    "82b081ec-3dd3-529c-8475-ab6c344590dd",
+                    "2",
+                    1,
+                    Some("other"),
+                ),
+                add_date: date_to_ts("2025-03-03T20:34:47.119721Z"),
+                last_modified: date_to_ts("2025-03-03T20:36:07.527863Z"),
+                children: vec![BookmarkNode::Folder {
+                    title: "Test".to_string(),
+                    meta: meta("96fee9ac-4bec-49a8-839c-6e101708a9d5", "5", 0, None),
+                    add_date: date_to_ts("2025-03-03T20:35:09.128841Z"),
+                    last_modified: date_to_ts("2025-03-03T20:36:11.273144Z"),
+                    children: vec![BookmarkNode::Link {
+                        title: "test.website".to_string(),
+                        url: "http://test.website/".to_string(),
+                        add_date: date_to_ts("2025-03-03T20:36:07.527863Z"),
+                        last_modified: date_to_ts("2025-03-03T20:36:07.527863Z"),
+                        tags: None,
+                        meta: BookmarkMeta {
+                            annotations: BTreeMap::from([(
+                                "power_bookmark_meta".to_string(),
+                                "".to_string(),
+                            )]),
+                            ..meta("5c91cdef-0664-4346-9dfc-85dd93e5b751", "6", 0, None)
+                        },
                     }],
+                }],
+            },
+            BookmarkNode::Folder {
+                title: "Mobile bookmarks".to_string(),
+                meta: meta(
+                    "4cf2e351-0e85-532b-bb37-df045d8f8d0f",
+                    "3",
+                    2,
+                    Some("synced"),
+                ),
+                add_date: date_to_ts("2025-03-03T20:34:47.119723Z"),
+                last_modified: date_to_ts("2025-03-03T20:34:47.119723Z"),
+                children: vec![],
+            },
+        ];
+        let result: Bookmarks = serde_json::from_str::<ChromeBookmarks>(input)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(expected, result.roots);
+        assert_eq!(
+            vec![BookmarkSource {
+                version: Some("1".to_string()),
+                ..Default::default()
+            }],
+            result.sources
+        );
+    }
+
+    #[test]
+    fn chrome_conversion_keeps_last_used_and_unknown_roots() {
+        let input = r#"{
+            "checksum": "",
+            "roots": {
+                "other": {
+                    "children": [{
+                        "date_added": "13385507767527863",
+                        "date_last_used": "13385703091000000",
+                        "name": "Used",
+                        "type": "url",
+                        "url": "http://used.website/"
+                    }],
+                    "date_added": "0",
+                    "name": "Other bookmarks",
+                    "type": "folder"
                 },
-                BookmarkNode::Folder {
-                    title: "Mobile bookmarks".to_string(),
-                    add_date: date_to_ts("2025-03-03T20:34:47.119723Z"),
-                    last_modified: date_to_ts("1601-01-01T00:00:00Z"),
-                    children: vec![],
+                "workspaces": {
+                    "children": [],
+                    "date_added": "0",
+                    "name": "Workspaces",
+                    "type": "folder"
                 },
+                "sync_transaction_version": "4"
+            },
+            "version": 1
+        }"#;
+        let result: Bookmarks = serde_json::from_str::<ChromeBookmarks>(input)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let roots = result
+            .roots
+            .iter()
+            .map(|root| match root {
+                BookmarkNode::Folder { meta, .. } => meta.root.as_deref().unwrap(),
+                _ => panic!("Expected the roots to be folders"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["other", "workspaces"], roots);
+        let BookmarkNode::Folder {
+            children, add_date, ..
+        } = &result.roots[0]
+        else {
+            unreachable!();
+        };
+        // A date of "0" was never recorded, not 1601-01-01
+        assert_eq!(Timestamp::UNIX_EPOCH, *add_date);
+        let BookmarkNode::Link { meta, .. } = &children[0] else {
+            panic!("Expected a link");
+        };
+        assert_eq!(Some(date_to_ts("2025-03-06T02:51:31Z")), meta.last_used);
+    }
+
+    #[test]
+    fn merge_trees_unifies_folders() {
+        let link = |url: &str, add_date: &str, tags: &[&str], source: &str| BookmarkNode::Link {
+            title: url.to_string(),
+            url: url.to_string(),
+            add_date: date_to_ts(add_date),
+            last_modified: date_to_ts(add_date),
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            meta: BookmarkMeta {
+                sources: vec![source.to_string()],
+                ..Default::default()
+            },
+        };
+        let folder = |title: &str, children| BookmarkNode::Folder {
+            title: title.to_string(),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            children,
+            meta: BookmarkMeta::default(),
+        };
+        let source = |label: &str| BookmarkSource {
+            path: Some(label.into()),
+            ..Default::default()
+        };
+        let work = Bookmarks::new(
+            vec![folder(
+                "Dev",
+                vec![
+                    link(
+                        "https://test.website/",
+                        "2025-03-01T00:00:00Z",
+                        &["test"],
+                        "work",
+                    ),
+                    link("https://work.website/", "2025-03-01T00:00:00Z", &[], "work"),
+                ],
+            )],
+            source("work"),
+        );
+        let home = Bookmarks::new(
+            vec![
+                folder(
+                    "Dev",
+                    vec![link(
+                        "HTTPS://Test.Website#top",
+                        "2025-02-01T00:00:00Z",
+                        &["test", "home"],
+                        "home",
+                    )],
+                ),
+                link("https://home.website/", "2025-02-01T00:00:00Z", &[], "home"),
+            ],
+            source("home"),
+        );
+
+        let expected = Bookmarks {
+            roots: vec![
+                folder(
+                    "Dev",
+                    vec![
+                        link(
+                            "https://test.website/",
+                            "2025-03-01T00:00:00Z",
+                            &["test"],
+                            "work",
+                        ),
+                        link("https://work.website/", "2025-03-01T00:00:00Z", &[], "work"),
+                        link(
+                            "HTTPS://Test.Website#top",
+                            "2025-02-01T00:00:00Z",
+                            &["test", "home"],
+                            "home",
+                        ),
+                    ],
+                ),
+                link("https://home.website/", "2025-02-01T00:00:00Z", &[], "home"),
             ],
+            sources: vec![source("work"), source("home")],
         };
-        let result: BookmarkNode = serde_json::from_str::<ChromeBookmarks>(input)
-            .unwrap()
-            .into();
-        assert_eq!(expected, result);
+        assert_eq!(Some(expected), merge_trees(vec![work, home]));
+        assert_eq!(None, merge_trees(vec![]));
+    }
+
+    #[test]
+    fn arrange_roots_gives_every_browser_the_same_layout() {
+        let firefox: Bookmarks =
+            serde_json::from_str::<FirefoxBookmarkNode>(include_str!("../../example-firefox.json"))
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let chrome: Bookmarks =
+            serde_json::from_str::<ChromeBookmarks>(include_str!("../../example-chromium.json"))
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let merged = merge_trees(vec![firefox, chrome]).unwrap();
+        let titles = |bookmarks: &Bookmarks| {
+            bookmarks
+                .roots
+                .iter()
+                .map(|root| match root {
+                    BookmarkNode::Folder { title, .. } => title.clone(),
+                    _ => panic!("Expected the roots to be folders"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut arranged = merged.clone();
+        arranged.arrange_roots(&RootOptions::default());
+        assert_eq!(
+            vec![
+                "Bookmarks Menu",
+                "Bookmarks Toolbar",
+                "Other Bookmarks",
+                "Mobile Bookmarks"
+            ],
+            titles(&arranged)
+        );
+
+        let mut arranged = merged;
+        arranged.arrange_roots(&RootOptions {
+            rename: BTreeMap::from([(RootKind::Toolbar, "Favorites".to_string())]),
+            hide: vec![RootKind::Mobile],
+            collapse: vec![RootKind::Other],
+        });
+        assert_eq!(
+            vec!["Bookmarks Menu", "Favorites", "Test Folder", "Test"],
+            titles(&arranged)
+        );
     }
 
     #[test]
     fn firefox_conversion_works() {
         let input = include_str!("../../example-firefox.json");
-        let expected = BookmarkNode::Folder {
-            title: "".to_string(),
-            add_date: date_to_ts("2025-02-26T20:17:28.186Z"),
-            last_modified: date_to_ts("2025-02-26T20:21:15.702Z"),
-            children: vec![
-                BookmarkNode::Folder {
-                    title: "menu".to_string(),
-                    add_date: date_to_ts("2025-02-26T20:17:28.186Z"),
-                    last_modified: date_to_ts("2025-02-26T20:19:11.666Z"),
-                    children: vec![],
-                },
-                BookmarkNode::Folder {
-                    title: "toolbar".to_string(),
-                    add_date: date_to_ts("2025-02-26T20:17:28.186Z"),
-                    last_modified: date_to_ts("2025-02-26T20:17:28.27Z"),
-                    children: vec![],
-                },
-                BookmarkNode::Folder {
-                    title: "unfiled".to_string(),
-                    add_date: date_to_ts("2025-02-26T20:17:28.186Z"),
+        let meta = |guid: &str, id: &str, index, root: Option<&str>| BookmarkMeta {
+            guid: Some(guid.to_string()),
+            id: Some(id.to_string()),
+            index: Some(index),
+            root: root.map(|root| root.to_string()),
+            ..Default::default()
+        };
+        let expected = vec![
+            BookmarkNode::Folder {
+                title: "menu".to_string(),
+                add_date: date_to_ts("2025-02-26T20:17:28.186Z"),
+                last_modified: date_to_ts("2025-02-26T20:19:11.666Z"),
+                children: vec![],
+                meta: meta("menu________", "2", 0, Some("bookmarksMenuFolder")),
+            },
+            BookmarkNode::Folder {
+                title: "toolbar".to_string(),
+                add_date: date_to_ts("2025-02-26T20:17:28.186Z"),
+                last_modified: date_to_ts("2025-02-26T20:17:28.27Z"),
+                children: vec![],
+                meta: meta("toolbar_____", "3", 1, Some("toolbarFolder")),
+            },
+            BookmarkNode::Folder {
+                title: "unfiled".to_string(),
+                add_date: date_to_ts("2025-02-26T20:17:28.186Z"),
+                last_modified: date_to_ts("2025-02-26T20:21:15.702Z"),
+                meta: meta("unfiled_____", "5", 3, Some("unfiledBookmarksFolder")),
+                children: vec![BookmarkNode::Folder {
+                    title: "Test Folder".to_string(),
+                    add_date: date_to_ts("2025-02-26T20:21:01.107Z"),
                     last_modified: date_to_ts("2025-02-26T20:21:15.702Z"),
-                    children: vec![BookmarkNode::Folder {
-                        title: "Test Folder".to_string(),
-                        add_date: date_to_ts("2025-02-26T20:21:01.107Z"),
+                    meta: meta("KYvW-dlvB_YS", "17", 0, None),
+                    children: vec![BookmarkNode::Link {
+                        title: "Test".to_string(),
+                        url: "https://test.website/".to_string(),
+                        add_date: date_to_ts("2025-02-26T20:18:25.441Z"),
                         last_modified: date_to_ts("2025-02-26T20:21:15.702Z"),
-                        children: vec![BookmarkNode::Link {
-                            title: "Test".to_string(),
-                            url: "https://test.website/".to_string(),
-                            add_date: date_to_ts("2025-02-26T20:18:25.441Z"),
-                            last_modified: date_to_ts("2025-02-26T20:21:15.702Z"),
-                            tags: Some(vec!["test".to_string(), "test1".to_string()]),
-                        }],
+                        tags: Some(vec!["test".to_string(), "test1".to_string()]),
+                        meta: BookmarkMeta {
+                            keyword: Some("test_keyword".to_string()),
+                            ..meta("mAQ-wHKmGwfH", "12", 0, None)
+                        },
                     }],
-                },
-                BookmarkNode::Folder {
-                    title: "mobile".to_string(),
-                    add_date: date_to_ts("2025-02-26T20:17:28.206Z"),
-                    last_modified: date_to_ts("2025-02-26T20:17:28.27Z"),
-                    children: vec![],
-                },
-            ],
-        };
-        let result: BookmarkNode = serde_json::from_str::<FirefoxBookmarkNode>(input)
+                }],
+            },
+            BookmarkNode::Folder {
+                title: "mobile".to_string(),
+                add_date: date_to_ts("2025-02-26T20:17:28.206Z"),
+                last_modified: date_to_ts("2025-02-26T20:17:28.27Z"),
+                children: vec![],
+                meta: meta("mobile______", "6", 4, Some("mobileFolder")),
+            },
+        ];
+        let result: Bookmarks = serde_json::from_str::<FirefoxBookmarkNode>(input)
             .unwrap()
-            .into();
-        assert_eq!(expected, result);
+            .try_into()
+            .unwrap();
+        assert_eq!(Bookmarks::new(expected, BookmarkSource::default()), result);
     }
 }
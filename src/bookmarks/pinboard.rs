@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use jiff::Timestamp;
+use serde::Deserialize;
+
+use crate::bookmarks::model::{split_tags, BookmarkMeta, BookmarkNode};
+
+/// A post from Pinboard's JSON export (`/export/format:json/`)
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct PinboardBookmark {
+    pub href: String,
+    /// The title, despite the name
+    #[serde(default)]
+    pub description: String,
+    /// The actual description
+    #[serde(default)]
+    pub extended: String,
+    /// MD5 of the URL
+    pub hash: Option<String>,
+    /// RFC 3339, e.g. `2025-02-26T20:18:25Z`
+    pub time: String,
+    /// "yes" or "no"
+    pub shared: Option<String>,
+    /// "yes" or "no"
+    pub toread: Option<String>,
+    /// Space-separated
+    #[serde(default)]
+    pub tags: String,
+}
+
+/// Attempts to parse a Pinboard JSON export as a flat list of links
+pub fn bookmarks_from_pinboard_json(data: &str) -> Result<Vec<BookmarkNode>> {
+    let posts: Vec<PinboardBookmark> = serde_json::from_str(data)?;
+    let children = posts
+        .into_iter()
+        .enumerate()
+        .map(|(index, post)| {
+            let add_date = post
+                .time
+                .parse::<Timestamp>()
+                .with_context(|| format!("Invalid time \"{}\" for {}", post.time, post.href))?;
+            let annotations = [("shared", post.shared), ("toread", post.toread)]
+                .into_iter()
+                .filter_map(|(key, value)| Some((key.to_string(), value?)))
+                .collect::<BTreeMap<_, _>>();
+            Ok(BookmarkNode::Link {
+                title: post.description,
+                url: post.href,
+                add_date,
+                last_modified: add_date,
+                tags: split_tags(&post.tags, char::is_whitespace),
+                meta: BookmarkMeta {
+                    id: post.hash,
+                    index: Some(index as u32),
+                    description: Some(post.extended).filter(|extended| !extended.is_empty()),
+                    annotations,
+                    ..Default::default()
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_pinboard_json() {
+        let input = r#"[
+            {
+                "href": "https://test.website/",
+                "description": "Test",
+                "extended": "A test website",
+                "meta": "0b1d2f3e4a5b6c7d8e9f0a1b2c3d4e5f",
+                "hash": "a8a6f4ba8e7b2c2a0b0c9c1e0d8f7a6b",
+                "time": "2025-02-26T20:18:25Z",
+                "shared": "no",
+                "toread": "yes",
+                "tags": "test  test1"
+            },
+            {"href": "https://untagged.website/", "description": "", "extended": "", "time": "2025-02-27T08:00:00Z", "tags": ""}
+        ]"#;
+        let date = "2025-02-26T20:18:25Z".parse().unwrap();
+        let untagged_date = "2025-02-27T08:00:00Z".parse().unwrap();
+        let expected = vec![
+            BookmarkNode::Link {
+                title: "Test".to_string(),
+                url: "https://test.website/".to_string(),
+                add_date: date,
+                last_modified: date,
+                tags: Some(vec!["test".to_string(), "test1".to_string()]),
+                meta: BookmarkMeta {
+                    id: Some("a8a6f4ba8e7b2c2a0b0c9c1e0d8f7a6b".to_string()),
+                    index: Some(0),
+                    description: Some("A test website".to_string()),
+                    annotations: BTreeMap::from([
+                        ("shared".to_string(), "no".to_string()),
+                        ("toread".to_string(), "yes".to_string()),
+                    ]),
+                    ..Default::default()
+                },
+            },
+            BookmarkNode::Link {
+                title: "".to_string(),
+                url: "https://untagged.website/".to_string(),
+                add_date: untagged_date,
+                last_modified: untagged_date,
+                tags: None,
+                meta: BookmarkMeta {
+                    index: Some(1),
+                    ..Default::default()
+                },
+            },
+        ];
+        assert_eq!(expected, bookmarks_from_pinboard_json(input).unwrap());
+
+        let err = bookmarks_from_pinboard_json(r#"[{"href": "x", "time": "yesterday"}]"#);
+        assert_eq!(
+            "Invalid time \"yesterday\" for x",
+            err.unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use jiff::Timestamp;
+use serde::Deserialize;
+
+use crate::bookmarks::model::{split_tags, BookmarkMeta, BookmarkNode};
+
+/// A row of Pocket's CSV export (`part_000000.csv`)
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct PocketBookmark {
+    #[serde(default)]
+    pub title: String,
+    pub url: String,
+    /// Seconds since the Unix epoch
+    pub time_added: String,
+    /// `|`-separated
+    #[serde(default)]
+    pub tags: String,
+    /// "unread" or "archive"
+    #[serde(default)]
+    pub status: String,
+}
+
+/// Attempts to parse a Pocket CSV export as a flat list of links
+pub fn bookmarks_from_pocket_csv(data: &[u8]) -> Result<Vec<BookmarkNode>> {
+    let mut children = vec![];
+    for row in csv::Reader::from_reader(data).deserialize() {
+        let row: PocketBookmark = row?;
+        let add_date = row
+            .time_added
+            .parse::<i64>()
+            .ok()
+            .and_then(|seconds| Timestamp::from_second(seconds).ok())
+            .with_context(|| {
+                format!("Invalid time_added \"{}\" for {}", row.time_added, row.url)
+            })?;
+        let annotations = Some(row.status)
+            .filter(|status| !status.is_empty())
+            .map(|status| BTreeMap::from([("status".to_string(), status)]))
+            .unwrap_or_default();
+        children.push(BookmarkNode::Link {
+            title: row.title,
+            url: row.url,
+            add_date,
+            last_modified: add_date,
+            tags: split_tags(&row.tags, |c| c == '|'),
+            meta: BookmarkMeta {
+                index: Some(children.len() as u32),
+                annotations,
+                ..Default::default()
+            },
+        });
+    }
+
+    Ok(children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_pocket_csv() {
+        let input = "title,url,time_added,tags,status\n\
+            Test,https://test.website/,1740601105,test|test1,archive\n";
+        let date = Timestamp::from_second(1740601105).unwrap();
+        let expected = vec![BookmarkNode::Link {
+            title: "Test".to_string(),
+            url: "https://test.website/".to_string(),
+            add_date: date,
+            last_modified: date,
+            tags: Some(vec!["test".to_string(), "test1".to_string()]),
+            meta: BookmarkMeta {
+                index: Some(0),
+                annotations: BTreeMap::from([("status".to_string(), "archive".to_string())]),
+                ..Default::default()
+            },
+        }];
+        assert_eq!(
+            expected,
+            bookmarks_from_pocket_csv(input.as_bytes()).unwrap()
+        );
+
+        let err = bookmarks_from_pocket_csv(b"title,url,time_added\nTest,x,soon\n");
+        assert_eq!(
+            "Invalid time_added \"soon\" for x",
+            err.unwrap_err().to_string()
+        );
+    }
+}
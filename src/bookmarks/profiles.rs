@@ -0,0 +1,344 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+
+/// Browsers whose profiles can be discovered on Linux
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Browser {
+    Firefox,
+    Chromium,
+    Chrome,
+    Brave,
+    Vivaldi,
+    Edge,
+}
+
+impl Browser {
+    /// Directories, relative to the home directory, that may hold this browser's profiles
+    ///
+    /// For Firefox this is the directory containing `profiles.ini`, for the Chromium family it's
+    /// the user data directory containing `Local State`. Native installs come first, followed by
+    /// Flatpak and Snap.
+    fn data_dirs(self) -> &'static [&'static str] {
+        match self {
+            Self::Firefox => &[
+                ".mozilla/firefox",
+                ".var/app/org.mozilla.firefox/.mozilla/firefox",
+                "snap/firefox/common/.mozilla/firefox",
+            ],
+            Self::Chromium => &[
+                ".config/chromium",
+                ".var/app/org.chromium.Chromium/config/chromium",
+                "snap/chromium/common/chromium",
+            ],
+            Self::Chrome => &[
+                ".config/google-chrome",
+                ".var/app/com.google.Chrome/config/google-chrome",
+            ],
+            Self::Brave => &[
+                ".config/BraveSoftware/Brave-Browser",
+                ".var/app/com.brave.Browser/config/BraveSoftware/Brave-Browser",
+                "snap/brave/current/.config/BraveSoftware/Brave-Browser",
+            ],
+            Self::Vivaldi => &[
+                ".config/vivaldi",
+                ".var/app/com.vivaldi.Vivaldi/config/vivaldi",
+            ],
+            Self::Edge => &[
+                ".config/microsoft-edge",
+                ".var/app/com.microsoft.Edge/config/microsoft-edge",
+            ],
+        }
+    }
+}
+
+impl fmt::Display for Browser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// A browser profile and the file its bookmarks live in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub browser: Browser,
+    /// The name shown in the browser's profile picker
+    pub name: String,
+    /// The profile directory
+    pub path: PathBuf,
+    /// `places.sqlite` for Firefox, `Bookmarks` for the Chromium family
+    pub bookmarks: PathBuf,
+    pub is_default: bool,
+}
+
+/// Finds every profile of the given browsers under `home`
+pub fn discover_profiles(home: &Path, browsers: &[Browser]) -> Vec<Profile> {
+    let mut profiles = vec![];
+    for &browser in browsers {
+        for data_dir in browser.data_dirs() {
+            let data_dir = home.join(data_dir);
+            if !data_dir.is_dir() {
+                continue;
+            }
+            match browser {
+                Browser::Firefox => profiles.extend(firefox_profiles(&data_dir)),
+                _ => profiles.extend(chromium_profiles(browser, &data_dir)),
+            }
+        }
+    }
+    profiles
+}
+
+/// Picks a single profile, either by name (or directory name) or the browser's default
+pub fn find_profile(home: &Path, browser: Browser, name: Option<&str>) -> Result<Profile> {
+    let profiles = discover_profiles(home, &[browser]);
+    if profiles.is_empty() {
+        bail!("No {browser} profiles found under {}", home.display());
+    }
+
+    let profile = match name {
+        Some(name) => profiles.iter().find(|profile| {
+            profile.name == name || profile.path.file_name().is_some_and(|dir| dir == name)
+        }),
+        None => profiles
+            .iter()
+            .find(|profile| profile.is_default)
+            .or(profiles.first()),
+    };
+    match profile {
+        Some(profile) => Ok(profile.clone()),
+        None => {
+            let names = profiles
+                .iter()
+                .map(|profile| profile.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            bail!(
+                "No {browser} profile named \"{}\" (found: {names})",
+                name.unwrap_or_default()
+            )
+        }
+    }
+}
+
+/// Reads `profiles.ini`, taking the default from `installs.ini` or the `[Install*]` sections when
+/// present since newer Firefox versions keep a separate default per installation
+fn firefox_profiles(data_dir: &Path) -> Vec<Profile> {
+    let Ok(profiles_ini) = fs::read_to_string(data_dir.join("profiles.ini")) else {
+        return vec![];
+    };
+    let profiles_ini = parse_ini(&profiles_ini);
+    let installs_ini = fs::read_to_string(data_dir.join("installs.ini"))
+        .map(|installs_ini| parse_ini(&installs_ini))
+        .unwrap_or_default();
+
+    let install_default = profiles_ini
+        .iter()
+        .chain(&installs_ini)
+        .find(|(section, keys)| {
+            !section.starts_with("Profile") && section != "General" && keys.contains_key("Default")
+        })
+        .map(|(_, keys)| keys["Default"].clone());
+
+    profiles_ini
+        .iter()
+        .filter(|(section, _)| section.starts_with("Profile"))
+        .filter_map(|(_, keys)| {
+            let relative_path = keys.get("Path")?;
+            let path = if keys
+                .get("IsRelative")
+                .is_none_or(|relative| relative == "1")
+            {
+                data_dir.join(relative_path)
+            } else {
+                PathBuf::from(relative_path)
+            };
+            let is_default = match &install_default {
+                Some(default) => default == relative_path,
+                None => keys.get("Default").is_some_and(|default| default == "1"),
+            };
+            Some(Profile {
+                browser: Browser::Firefox,
+                name: keys.get("Name").cloned().unwrap_or_default(),
+                bookmarks: path.join("places.sqlite"),
+                path,
+                is_default,
+            })
+        })
+        .collect()
+}
+
+/// Lists the profile directories holding a `Bookmarks` file, named after `Local State`'s
+/// `profile.info_cache` when it's readable
+fn chromium_profiles(browser: Browser, data_dir: &Path) -> Vec<Profile> {
+    let local_state = fs::read(data_dir.join("Local State"))
+        .ok()
+        .and_then(|local_state| serde_json::from_slice::<Value>(&local_state).ok())
+        .unwrap_or_default();
+    let last_used = local_state["profile"]["last_used"]
+        .as_str()
+        .unwrap_or("Default");
+
+    let Ok(entries) = fs::read_dir(data_dir) else {
+        return vec![];
+    };
+    let mut profiles = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("Bookmarks").is_file())
+        .map(|path| {
+            let dir = path.file_name().unwrap().to_string_lossy().to_string();
+            let name = local_state["profile"]["info_cache"][&dir]["name"]
+                .as_str()
+                .unwrap_or(&dir)
+                .to_string();
+            Profile {
+                browser,
+                name,
+                bookmarks: path.join("Bookmarks"),
+                is_default: dir == last_used,
+                path,
+            }
+        })
+        .collect::<Vec<_>>();
+    profiles.sort_by(|a, b| a.path.cmp(&b.path));
+    profiles
+}
+
+/// A bare-bones INI parser, enough for `profiles.ini` and `installs.ini`
+fn parse_ini(data: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut sections: Vec<(String, HashMap<String, String>)> = vec![];
+    for line in data.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with([';', '#']) {
+            continue;
+        }
+        if let Some(section) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            sections.push((section.to_string(), HashMap::new()));
+        } else if let (Some((key, value)), Some((_, keys))) =
+            (line.split_once('='), sections.last_mut())
+        {
+            keys.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn write(path: PathBuf, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn fake_home() -> tempfile::TempDir {
+        let home = tempfile::tempdir().unwrap();
+        let firefox = home.path().join(".mozilla/firefox");
+        write(
+            firefox.join("profiles.ini"),
+            "[Profile1]\nName=default\nIsRelative=1\nPath=abcd.default\nDefault=1\n\n\
+             [Profile0]\nName=default-release\nIsRelative=1\nPath=efgh.default-release\n\n\
+             [General]\nStartWithLastProfile=1\nVersion=2\n",
+        );
+        write(
+            firefox.join("installs.ini"),
+            "[4F96D1932A9F858E]\nDefault=efgh.default-release\nLocked=1\n",
+        );
+
+        let chromium = home
+            .path()
+            .join(".var/app/org.chromium.Chromium/config/chromium");
+        write(chromium.join("Default/Bookmarks"), "{}");
+        write(chromium.join("Profile 1/Bookmarks"), "{}");
+        fs::create_dir_all(chromium.join("System Profile")).unwrap();
+        write(
+            chromium.join("Local State"),
+            r#"{"profile": {"last_used": "Profile 1", "info_cache": {
+                "Default": {"name": "Personal"},
+                "Profile 1": {"name": "Work"}
+            }}}"#,
+        );
+        home
+    }
+
+    #[test]
+    fn discover_firefox_profiles() {
+        let home = fake_home();
+        let firefox = home.path().join(".mozilla/firefox");
+        let expected = vec![
+            Profile {
+                browser: Browser::Firefox,
+                name: "default".to_string(),
+                path: firefox.join("abcd.default"),
+                bookmarks: firefox.join("abcd.default/places.sqlite"),
+                is_default: false,
+            },
+            Profile {
+                browser: Browser::Firefox,
+                name: "default-release".to_string(),
+                path: firefox.join("efgh.default-release"),
+                bookmarks: firefox.join("efgh.default-release/places.sqlite"),
+                is_default: true,
+            },
+        ];
+        assert_eq!(
+            expected,
+            discover_profiles(home.path(), &[Browser::Firefox])
+        );
+    }
+
+    #[test]
+    fn discover_flatpak_chromium_profiles() {
+        let home = fake_home();
+        let chromium = home
+            .path()
+            .join(".var/app/org.chromium.Chromium/config/chromium");
+        let expected = vec![
+            Profile {
+                browser: Browser::Chromium,
+                name: "Personal".to_string(),
+                path: chromium.join("Default"),
+                bookmarks: chromium.join("Default/Bookmarks"),
+                is_default: false,
+            },
+            Profile {
+                browser: Browser::Chromium,
+                name: "Work".to_string(),
+                path: chromium.join("Profile 1"),
+                bookmarks: chromium.join("Profile 1/Bookmarks"),
+                is_default: true,
+            },
+        ];
+        assert_eq!(
+            expected,
+            discover_profiles(home.path(), &[Browser::Chromium])
+        );
+        assert!(discover_profiles(home.path(), &[Browser::Brave]).is_empty());
+    }
+
+    #[test]
+    fn find_profile_works() {
+        let home = fake_home();
+        let find = |browser, name| find_profile(home.path(), browser, name).map(|p| p.name);
+        assert_eq!("default-release", find(Browser::Firefox, None).unwrap());
+        assert_eq!("default", find(Browser::Firefox, Some("default")).unwrap());
+        assert_eq!("Work", find(Browser::Chromium, None).unwrap());
+        assert_eq!(
+            "Personal",
+            find(Browser::Chromium, Some("Default")).unwrap()
+        );
+        assert!(find(Browser::Chromium, Some("Nope")).is_err());
+        assert!(find(Browser::Vivaldi, None).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use jiff::Timestamp;
+
+use crate::bookmarks::model::{BookmarkMeta, BookmarkNode};
+
+/// Parses qutebrowser's `bookmarks/urls`, where each line is a URL followed by its title
+///
+/// The file holds no dates, so `date` (usually its modification time) is used for every bookmark.
+pub fn bookmarks_from_qutebrowser_urls(data: &str, date: Timestamp) -> Vec<BookmarkNode> {
+    lines(data)
+        .map(|line| match line.split_once(char::is_whitespace) {
+            Some((url, title)) => (url, title.trim()),
+            None => (line, ""),
+        })
+        .enumerate()
+        .map(|(index, (url, title))| link(title, url, None, index, date))
+        .collect()
+}
+
+/// Parses qutebrowser's `quickmarks`, where each line is a name followed by a URL
+///
+/// Names may contain spaces, so the URL is whatever follows the last one. The name becomes both
+/// the title and the keyword, since that's what `:quickmark-load` takes.
+pub fn bookmarks_from_qutebrowser_quickmarks(data: &str, date: Timestamp) -> Vec<BookmarkNode> {
+    lines(data)
+        .map(|line| match line.rsplit_once(char::is_whitespace) {
+            Some((name, url)) => (name.trim(), url),
+            None => ("", line),
+        })
+        .enumerate()
+        .map(|(index, (name, url))| link(name, url, Some(name), index, date))
+        .collect()
+}
+
+/// Whether every line of `data` starts (or, for quickmarks, ends) with a URL
+pub fn is_qutebrowser_file(data: &str, quickmarks: bool) -> bool {
+    let mut lines = lines(data).peekable();
+    lines.peek().is_some()
+        && lines.all(|line| {
+            let mut tokens = line.split_whitespace();
+            let token = if quickmarks {
+                tokens.next_back()
+            } else {
+                tokens.next()
+            };
+            token.is_some_and(looks_like_url)
+        })
+}
+
+fn lines(data: &str) -> impl Iterator<Item = &str> {
+    data.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+/// A scheme followed by something, e.g. `https://...` or `about:blank`
+fn looks_like_url(token: &str) -> bool {
+    token.split_once(':').is_some_and(|(scheme, rest)| {
+        !rest.is_empty()
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+fn link(
+    title: &str,
+    url: &str,
+    keyword: Option<&str>,
+    index: usize,
+    date: Timestamp,
+) -> BookmarkNode {
+    BookmarkNode::Link {
+        title: title.to_string(),
+        url: url.to_string(),
+        add_date: date,
+        last_modified: date,
+        tags: None,
+        meta: BookmarkMeta {
+            index: Some(index as u32),
+            keyword: keyword.map(|keyword| keyword.to_string()),
+            ..Default::default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_qutebrowser_files() {
+        let date = Timestamp::from_second(1740601105).unwrap();
+        let urls = "https://test.website/ Test website\n\nhttps://untitled.website/\n";
+        let quickmarks = "test site https://test.website/\n";
+        assert!(is_qutebrowser_file(urls, false));
+        assert!(!is_qutebrowser_file(urls, true));
+        assert!(is_qutebrowser_file(quickmarks, true));
+        assert!(!is_qutebrowser_file(quickmarks, false));
+        assert!(!is_qutebrowser_file("", false));
+        assert!(!is_qutebrowser_file("not bookmarks", false));
+
+        assert_eq!(
+            vec![
+                link("Test website", "https://test.website/", None, 0, date),
+                link("", "https://untitled.website/", None, 1, date),
+            ],
+            bookmarks_from_qutebrowser_urls(urls, date)
+        );
+        assert_eq!(
+            vec![link(
+                "test site",
+                "https://test.website/",
+                Some("test site"),
+                0,
+                date
+            )],
+            bookmarks_from_qutebrowser_quickmarks(quickmarks, date)
+        );
+    }
+}
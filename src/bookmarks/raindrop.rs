@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use jiff::Timestamp;
+use serde::Deserialize;
+
+use crate::bookmarks::model::{folder_by_path, split_tags, BookmarkMeta, BookmarkNode};
+
+/// A row of Raindrop.io's CSV export
+///
+/// Its HTML export is a Netscape bookmark file and goes through the HTML parser instead.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct RaindropBookmark {
+    pub id: Option<String>,
+    #[serde(default)]
+    pub title: String,
+    /// The user's own note
+    #[serde(default)]
+    pub note: String,
+    /// The page description, as fetched or edited in Raindrop
+    #[serde(default)]
+    pub excerpt: String,
+    pub url: String,
+    /// The collection, with nested collections separated by `/`
+    #[serde(default)]
+    pub folder: String,
+    /// Comma-separated
+    #[serde(default)]
+    pub tags: String,
+    /// RFC 3339, e.g. `2025-02-26T20:18:25.441Z`
+    pub created: String,
+    /// URL of the cover image
+    #[serde(default)]
+    pub cover: String,
+    /// Every highlight, one after another
+    #[serde(default)]
+    pub highlights: String,
+    /// "true" or "false"
+    #[serde(default)]
+    pub favorite: String,
+}
+
+/// Attempts to parse a Raindrop.io CSV export, turning collections into folders
+pub fn bookmarks_from_raindrop_csv(data: &[u8]) -> Result<Vec<BookmarkNode>> {
+    let mut children = vec![];
+    for row in csv::Reader::from_reader(data).deserialize() {
+        let row: RaindropBookmark = row?;
+        let add_date = row
+            .created
+            .parse::<Timestamp>()
+            .with_context(|| format!("Invalid created \"{}\" for {}", row.created, row.url))?;
+        let annotations = [
+            ("note", row.note),
+            ("cover", row.cover),
+            ("highlights", row.highlights),
+            ("favorite", row.favorite),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (key.to_string(), value))
+        .collect::<BTreeMap<_, _>>();
+
+        let path = row
+            .folder
+            .split('/')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        let folder = folder_by_path(&mut children, &path);
+        folder.push(BookmarkNode::Link {
+            title: row.title,
+            url: row.url,
+            add_date,
+            last_modified: add_date,
+            tags: split_tags(&row.tags, |c| c == ','),
+            meta: BookmarkMeta {
+                id: row.id,
+                index: Some(folder.len() as u32),
+                description: Some(row.excerpt).filter(|excerpt| !excerpt.is_empty()),
+                annotations,
+                ..Default::default()
+            },
+        });
+    }
+
+    Ok(children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_raindrop_csv() {
+        let input = "id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite\n\
+            1,Test,My note,A test website,https://test.website/,Dev/Rust,\"test, test1\",2025-02-26T20:18:25.441Z,https://test.website/cover.png,\"Highlight: quoted\",true\n\
+            2,Other,,,https://other.website/,Dev,,2025-02-27T08:00:00.000Z,,,false\n\
+            3,Unsorted,,,https://unsorted.website/,,,2025-02-27T08:00:00.000Z,,,false\n";
+        let date = "2025-02-26T20:18:25.441Z".parse().unwrap();
+        let other_date = "2025-02-27T08:00:00Z".parse().unwrap();
+        let link = |id: &str, title: &str, index| BookmarkNode::Link {
+            title: title.to_string(),
+            url: format!("https://{}.website/", title.to_lowercase()),
+            add_date: other_date,
+            last_modified: other_date,
+            tags: None,
+            meta: BookmarkMeta {
+                id: Some(id.to_string()),
+                index: Some(index),
+                annotations: BTreeMap::from([("favorite".to_string(), "false".to_string())]),
+                ..Default::default()
+            },
+        };
+        let folder = |title: &str, index, children| BookmarkNode::Folder {
+            title: title.to_string(),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            children,
+            meta: BookmarkMeta {
+                index: Some(index),
+                ..Default::default()
+            },
+        };
+        let expected = vec![
+            folder(
+                "Dev",
+                0,
+                vec![
+                    folder(
+                        "Rust",
+                        0,
+                        vec![BookmarkNode::Link {
+                            title: "Test".to_string(),
+                            url: "https://test.website/".to_string(),
+                            add_date: date,
+                            last_modified: date,
+                            tags: Some(vec!["test".to_string(), "test1".to_string()]),
+                            meta: BookmarkMeta {
+                                id: Some("1".to_string()),
+                                index: Some(0),
+                                description: Some("A test website".to_string()),
+                                annotations: BTreeMap::from([
+                                    (
+                                        "cover".to_string(),
+                                        "https://test.website/cover.png".to_string(),
+                                    ),
+                                    ("favorite".to_string(), "true".to_string()),
+                                    ("highlights".to_string(), "Highlight: quoted".to_string()),
+                                    ("note".to_string(), "My note".to_string()),
+                                ]),
+                                ..Default::default()
+                            },
+                        }],
+                    ),
+                    link("2", "Other", 1),
+                ],
+            ),
+            link("3", "Unsorted", 1),
+        ];
+        assert_eq!(
+            expected,
+            bookmarks_from_raindrop_csv(input.as_bytes()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use jiff::Timestamp;
+use serde::Deserialize;
+
+use crate::bookmarks::model::{BookmarkMeta, BookmarkNode};
+
+/// Title of the folder Safari keeps the Reading List in
+const READING_LIST: &str = "com.apple.ReadingList";
+
+/// Top-level lists with a special meaning, named by their `Title`
+const ROOT_TITLES: [&str; 3] = ["BookmarksBar", "BookmarksMenu", READING_LIST];
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "WebBookmarkType")]
+pub enum SafariBookmarkNode {
+    #[serde(rename = "WebBookmarkTypeList")]
+    List {
+        #[serde(rename = "WebBookmarkUUID")]
+        uuid: Option<String>,
+        #[serde(rename = "Title", default)]
+        title: String,
+        #[serde(rename = "Children", default)]
+        children: Vec<SafariBookmarkNode>,
+    },
+    #[serde(rename = "WebBookmarkTypeLeaf")]
+    Leaf {
+        #[serde(rename = "WebBookmarkUUID")]
+        uuid: Option<String>,
+        #[serde(rename = "URLString")]
+        url: String,
+        #[serde(rename = "URIDictionary", default)]
+        uri_dictionary: BTreeMap<String, String>,
+        /// Only set for Reading List entries
+        #[serde(rename = "ReadingList")]
+        reading_list: Option<SafariReadingList>,
+    },
+    /// Stand-ins for History and other built-in collections, which hold no bookmarks
+    #[serde(rename = "WebBookmarkTypeProxy")]
+    Proxy {
+        #[serde(rename = "Title", default)]
+        title: String,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct SafariReadingList {
+    pub date_added: Option<plist::Date>,
+    pub date_last_viewed: Option<plist::Date>,
+    pub preview_text: Option<String>,
+}
+
+/// Attempts to parse a Safari `Bookmarks.plist`, in either its binary or XML form
+pub fn bookmarks_from_safari_plist(data: &[u8]) -> Result<Vec<BookmarkNode>> {
+    let root: SafariBookmarkNode = plist::from_bytes(data)?;
+    // The top-level list is an untitled container for the real roots
+    match convert(root, None, 0) {
+        Some(BookmarkNode::Folder { children, .. }) => Ok(children),
+        _ => Ok(vec![]),
+    }
+}
+
+/// Converts a node, dropping proxies. Safari only dates Reading List entries, so everything else
+/// is dated at the Unix epoch.
+fn convert(node: SafariBookmarkNode, index: Option<u32>, depth: usize) -> Option<BookmarkNode> {
+    match node {
+        SafariBookmarkNode::List {
+            uuid,
+            title,
+            children,
+        } => {
+            let children = children
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, child)| convert(child, Some(index as u32), depth + 1))
+                .collect();
+            let root = (depth == 1 && ROOT_TITLES.contains(&title.as_str())).then(|| title.clone());
+            // The other roots have readable titles already
+            let title = match root.as_deref() {
+                Some(READING_LIST) => "Reading List".to_string(),
+                _ => title,
+            };
+            Some(BookmarkNode::Folder {
+                title,
+                add_date: Timestamp::UNIX_EPOCH,
+                last_modified: Timestamp::UNIX_EPOCH,
+                children,
+                meta: BookmarkMeta {
+                    guid: uuid,
+                    index,
+                    root,
+                    ..Default::default()
+                },
+            })
+        }
+        SafariBookmarkNode::Leaf {
+            uuid,
+            url,
+            mut uri_dictionary,
+            reading_list,
+        } => {
+            let date = |date: Option<plist::Date>| {
+                date.and_then(|date| Timestamp::try_from(std::time::SystemTime::from(date)).ok())
+            };
+            let (add_date, last_used, description) = match reading_list {
+                Some(reading_list) => (
+                    date(reading_list.date_added),
+                    date(reading_list.date_last_viewed),
+                    reading_list.preview_text,
+                ),
+                None => (None, None, None),
+            };
+            let add_date = add_date.unwrap_or(Timestamp::UNIX_EPOCH);
+            Some(BookmarkNode::Link {
+                title: uri_dictionary.remove("title").unwrap_or_default(),
+                url,
+                add_date,
+                last_modified: add_date,
+                tags: None,
+                meta: BookmarkMeta {
+                    guid: uuid,
+                    index,
+                    description,
+                    last_used,
+                    annotations: uri_dictionary,
+                    ..Default::default()
+                },
+            })
+        }
+        SafariBookmarkNode::Proxy { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn safari_xml_and_binary_plists_work() {
+        let meta = |uuid: &str, index, root: Option<&str>| BookmarkMeta {
+            guid: Some(uuid.to_string()),
+            index,
+            root: root.map(|root| root.to_string()),
+            ..Default::default()
+        };
+        let folder = |title: &str, meta, children| BookmarkNode::Folder {
+            title: title.to_string(),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            children,
+            meta,
+        };
+        let link = |title: &str, url: &str, meta| BookmarkNode::Link {
+            title: title.to_string(),
+            url: url.to_string(),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            tags: None,
+            meta,
+        };
+        let article_date = "2025-02-26T20:18:25Z".parse().unwrap();
+        let expected = vec![
+            folder(
+                "BookmarksBar",
+                meta(
+                    "5A0D3F77-2C1B-4A8E-9D3F-1E7C6B5A4F32",
+                    Some(1),
+                    Some("BookmarksBar"),
+                ),
+                vec![
+                    link(
+                        "Test",
+                        "https://test.website/",
+                        meta("B7E4C1A2-3D5F-4E6A-8B9C-0D1E2F3A4B53", Some(0), None),
+                    ),
+                    folder(
+                        "Test Folder",
+                        meta("C9F2E3D4-5B6A-4C7D-8E9F-1A2B3C4D5E64", Some(1), None),
+                        vec![link(
+                            "Nested",
+                            "https://nested.website/",
+                            meta("D1A2B3C4-6E7F-4A8B-9C0D-2E3F4A5B6C75", Some(0), None),
+                        )],
+                    ),
+                ],
+            ),
+            folder(
+                "BookmarksMenu",
+                meta(
+                    "E2B3C4D5-7F8A-4B9C-0D1E-3F4A5B6C7D86",
+                    Some(2),
+                    Some("BookmarksMenu"),
+                ),
+                vec![],
+            ),
+            folder(
+                "Reading List",
+                meta(
+                    "F3C4D5E6-8A9B-4C0D-1E2F-4A5B6C7D8E97",
+                    Some(3),
+                    Some(READING_LIST),
+                ),
+                vec![BookmarkNode::Link {
+                    title: "An Article".to_string(),
+                    url: "https://article.website/post".to_string(),
+                    add_date: article_date,
+                    last_modified: article_date,
+                    tags: None,
+                    meta: BookmarkMeta {
+                        description: Some("The first few lines of the article.".to_string()),
+                        last_used: Some("2025-02-27T08:00:00Z".parse().unwrap()),
+                        ..meta("A4D5E6F7-9B0C-4D1E-2F3A-5B6C7D8E9FA8", Some(0), None)
+                    },
+                }],
+            ),
+        ];
+
+        let xml = bookmarks_from_safari_plist(include_bytes!("../../example-safari.plist"));
+        assert_eq!(expected, xml.unwrap());
+        let binary =
+            bookmarks_from_safari_plist(include_bytes!("../../example-safari-binary.plist"));
+        assert_eq!(expected, binary.unwrap());
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::VecDeque;
+
+use crate::bookmarks::model::{BookmarkNode, Bookmarks};
+
+/// A node reached by a traversal, along with where it sits in the tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct Visit<'a> {
+    pub node: &'a BookmarkNode,
+    /// Titles of the folders above the node, outermost first
+    pub path: Vec<&'a str>,
+}
+
+impl<'a> Visit<'a> {
+    /// How many folders the node is nested in, 0 for roots
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    /// The visits for a folder's children, or nothing for links and separators
+    fn children(&self) -> impl DoubleEndedIterator<Item = Visit<'a>> + '_ {
+        let (title, children) = match self.node {
+            BookmarkNode::Folder {
+                title, children, ..
+            } => (title.as_str(), children.as_slice()),
+            _ => ("", [].as_slice()),
+        };
+        children.iter().map(move |node| {
+            let mut path = self.path.clone();
+            path.push(title);
+            Visit { node, path }
+        })
+    }
+}
+
+/// Depth-first (pre-order) iterator: each folder comes right before its contents
+pub struct DepthFirst<'a> {
+    stack: Vec<Visit<'a>>,
+}
+
+impl<'a> DepthFirst<'a> {
+    pub fn new(roots: &'a [BookmarkNode]) -> Self {
+        Self {
+            stack: roots.iter().rev().map(root_visit).collect(),
+        }
+    }
+}
+
+impl<'a> Iterator for DepthFirst<'a> {
+    type Item = Visit<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let visit = self.stack.pop()?;
+        self.stack.extend(visit.children().rev());
+        Some(visit)
+    }
+}
+
+/// Breadth-first iterator: every node at one depth comes before any node deeper down
+pub struct BreadthFirst<'a> {
+    queue: VecDeque<Visit<'a>>,
+}
+
+impl<'a> BreadthFirst<'a> {
+    pub fn new(roots: &'a [BookmarkNode]) -> Self {
+        Self {
+            queue: roots.iter().map(root_visit).collect(),
+        }
+    }
+}
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = Visit<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let visit = self.queue.pop_front()?;
+        self.queue.extend(visit.children());
+        Some(visit)
+    }
+}
+
+fn root_visit(node: &BookmarkNode) -> Visit<'_> {
+    Visit { node, path: vec![] }
+}
+
+fn is_link(visit: &Visit) -> bool {
+    matches!(visit.node, BookmarkNode::Link { .. })
+}
+
+fn is_folder(visit: &Visit) -> bool {
+    matches!(visit.node, BookmarkNode::Folder { .. })
+}
+
+/// Calls `visit` on `node` and then on each of its descendants, depth-first
+fn walk_mut(
+    node: &mut BookmarkNode,
+    path: &mut Vec<String>,
+    visit: &mut impl FnMut(&mut BookmarkNode, &[String]),
+) {
+    visit(node, path);
+    if let BookmarkNode::Folder {
+        title, children, ..
+    } = node
+    {
+        path.push(title.clone());
+        for child in children {
+            walk_mut(child, path, visit);
+        }
+        path.pop();
+    }
+}
+
+impl BookmarkNode {
+    /// This node and everything in it, depth-first, with paths relative to this node
+    pub fn depth_first(&self) -> DepthFirst<'_> {
+        DepthFirst::new(std::slice::from_ref(self))
+    }
+
+    /// This node and everything in it, breadth-first, with paths relative to this node
+    pub fn breadth_first(&self) -> BreadthFirst<'_> {
+        BreadthFirst::new(std::slice::from_ref(self))
+    }
+
+    /// Every link in (or at) this node, depth-first
+    pub fn links(&self) -> impl Iterator<Item = Visit<'_>> {
+        self.depth_first().filter(is_link)
+    }
+
+    /// Every folder in (or at) this node, depth-first
+    pub fn folders(&self) -> impl Iterator<Item = Visit<'_>> {
+        self.depth_first().filter(is_folder)
+    }
+
+    /// Calls `visit` with every node and the titles of the folders above it, depth-first
+    ///
+    /// A folder is visited before its children, so changes it makes to them are seen when they're
+    /// visited in turn.
+    pub fn walk_mut(&mut self, visit: &mut impl FnMut(&mut BookmarkNode, &[String])) {
+        walk_mut(self, &mut vec![], visit);
+    }
+}
+
+impl Bookmarks {
+    /// Every node in every root, depth-first
+    pub fn depth_first(&self) -> DepthFirst<'_> {
+        DepthFirst::new(&self.roots)
+    }
+
+    /// Every node in every root, breadth-first
+    pub fn breadth_first(&self) -> BreadthFirst<'_> {
+        BreadthFirst::new(&self.roots)
+    }
+
+    /// Every link, depth-first
+    pub fn links(&self) -> impl Iterator<Item = Visit<'_>> {
+        self.depth_first().filter(is_link)
+    }
+
+    /// Every folder, depth-first
+    pub fn folders(&self) -> impl Iterator<Item = Visit<'_>> {
+        self.depth_first().filter(is_folder)
+    }
+
+    /// [`BookmarkNode::walk_mut`] over every root
+    pub fn walk_mut(&mut self, visit: &mut impl FnMut(&mut BookmarkNode, &[String])) {
+        for root in &mut self.roots {
+            root.walk_mut(visit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmarks::model::{BookmarkMeta, BookmarkSource};
+    use jiff::Timestamp;
+    use pretty_assertions::assert_eq;
+
+    fn link(title: &str) -> BookmarkNode {
+        BookmarkNode::Link {
+            title: title.to_string(),
+            url: format!("https://{}.website/", title.to_lowercase()),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            tags: None,
+            meta: BookmarkMeta::default(),
+        }
+    }
+
+    fn folder(title: &str, children: Vec<BookmarkNode>) -> BookmarkNode {
+        BookmarkNode::Folder {
+            title: title.to_string(),
+            add_date: Timestamp::UNIX_EPOCH,
+            last_modified: Timestamp::UNIX_EPOCH,
+            children,
+            meta: BookmarkMeta::default(),
+        }
+    }
+
+    fn title(node: &BookmarkNode) -> &str {
+        match node {
+            BookmarkNode::Link { title, .. } | BookmarkNode::Folder { title, .. } => title,
+            BookmarkNode::Separator { .. } => "-",
+        }
+    }
+
+    fn sample() -> Bookmarks {
+        Bookmarks::new(
+            vec![
+                folder(
+                    "Toolbar",
+                    vec![folder("Dev", vec![link("Rust")]), link("News")],
+                ),
+                link("Loose"),
+            ],
+            BookmarkSource::default(),
+        )
+    }
+
+    #[test]
+    fn depth_first_yields_paths_and_depths() {
+        let bookmarks = sample();
+        let visits = bookmarks
+            .depth_first()
+            .map(|visit| (title(visit.node), visit.path.join("/"), visit.depth()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("Toolbar", "".to_string(), 0),
+                ("Dev", "Toolbar".to_string(), 1),
+                ("Rust", "Toolbar/Dev".to_string(), 2),
+                ("News", "Toolbar".to_string(), 1),
+                ("Loose", "".to_string(), 0),
+            ],
+            visits
+        );
+    }
+
+    #[test]
+    fn breadth_first_goes_level_by_level() {
+        let bookmarks = sample();
+        let titles = bookmarks
+            .breadth_first()
+            .map(|visit| title(visit.node))
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Toolbar", "Loose", "Dev", "News", "Rust"], titles);
+
+        let Some(toolbar) = bookmarks.roots.first() else {
+            unreachable!();
+        };
+        let depths = toolbar
+            .breadth_first()
+            .map(|visit| visit.depth())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 1, 2], depths);
+    }
+
+    #[test]
+    fn links_and_folders_work() {
+        let bookmarks = sample();
+        let links = bookmarks
+            .links()
+            .map(|visit| title(visit.node))
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Rust", "News", "Loose"], links);
+        let folders = bookmarks
+            .folders()
+            .map(|visit| title(visit.node))
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Toolbar", "Dev"], folders);
+    }
+
+    #[test]
+    fn walk_mut_sees_paths_and_changes() {
+        let mut bookmarks = sample();
+        bookmarks.walk_mut(&mut |node, path| {
+            if let BookmarkNode::Link { title, .. } = node {
+                *title = format!("{}/{title}", path.join("/"))
+                    .trim_start_matches('/')
+                    .to_string();
+            }
+        });
+        let links = bookmarks
+            .links()
+            .map(|visit| title(visit.node))
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Toolbar/Dev/Rust", "Toolbar/News", "Loose"], links);
+    }
+}
@@ -0,0 +1,161 @@
+use jiff::Timestamp;
+use scraper::{Html, Selector};
+
+use crate::bookmarks::model::{BookmarkMeta, BookmarkNode};
+
+/// The comment w3m closes every section of its `bookmark.html` with
+pub const W3M_SECTION_END: &str = "<!--End of section (do not delete this comment)-->";
+
+/// The title Lynx gives its bookmark files
+pub const LYNX_TITLE: &str = "<title>Bookmark file</title>";
+
+/// Parses a w3m `bookmark.html` or a Lynx `lynx_bookmarks.html`
+///
+/// Both are plain HTML lists of links, with w3m grouping them into `<h2>` sections that become
+/// root folders. Neither records dates, so `date` (usually the file's modification time) is used for
+/// everything.
+pub fn bookmarks_from_w3m_html(data: &str, date: Timestamp) -> Vec<BookmarkNode> {
+    let document = Html::parse_document(data);
+    let items = Selector::parse("h2, a[href]").unwrap();
+
+    let text = |element: scraper::ElementRef| element.text().collect::<String>().trim().to_string();
+    let folder = |title: String, index: usize| BookmarkNode::Folder {
+        title,
+        add_date: date,
+        last_modified: date,
+        children: vec![],
+        meta: BookmarkMeta {
+            index: Some(index as u32),
+            ..Default::default()
+        },
+    };
+
+    let mut children = vec![];
+    // Links before the first section are roots themselves
+    let mut section = None;
+    for item in document.select(&items) {
+        if item.value().name() == "h2" {
+            children.push(folder(text(item), children.len()));
+            section = Some(children.len() - 1);
+            continue;
+        }
+        let list = match section.map(|section| &mut children[section]) {
+            Some(BookmarkNode::Folder { children, .. }) => children,
+            _ => &mut children,
+        };
+        list.push(BookmarkNode::Link {
+            title: text(item),
+            url: item.value().attr("href").unwrap_or_default().to_string(),
+            add_date: date,
+            last_modified: date,
+            tags: None,
+            meta: BookmarkMeta {
+                index: Some(list.len() as u32),
+                ..Default::default()
+            },
+        });
+    }
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn link(title: &str, url: &str, index: u32, date: Timestamp) -> BookmarkNode {
+        BookmarkNode::Link {
+            title: title.to_string(),
+            url: url.to_string(),
+            add_date: date,
+            last_modified: date,
+            tags: None,
+            meta: BookmarkMeta {
+                index: Some(index),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn folder(
+        title: &str,
+        index: u32,
+        children: Vec<BookmarkNode>,
+        date: Timestamp,
+    ) -> BookmarkNode {
+        BookmarkNode::Folder {
+            title: title.to_string(),
+            add_date: date,
+            last_modified: date,
+            children,
+            meta: BookmarkMeta {
+                index: Some(index),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn parse_w3m_bookmarks() {
+        let input = r#"<html><head><title>Bookmarks</title></head>
+<body>
+<h1>Bookmarks</h1>
+<h2>Default</h2>
+<ul>
+<li><a href="http://w3m.sourceforge.net/">w3m homepage</a>
+<!--End of section (do not delete this comment)-->
+</ul>
+<h2>Rust</h2>
+<ul>
+<li><a href="https://test.website/">Test</a>
+<li><a href="https://other.website/">Other</a>
+<!--End of section (do not delete this comment)-->
+</ul>
+</body>
+</html>
+"#;
+        let date = Timestamp::from_second(1740601105).unwrap();
+        let expected = vec![
+            folder(
+                "Default",
+                0,
+                vec![link("w3m homepage", "http://w3m.sourceforge.net/", 0, date)],
+                date,
+            ),
+            folder(
+                "Rust",
+                1,
+                vec![
+                    link("Test", "https://test.website/", 0, date),
+                    link("Other", "https://other.website/", 1, date),
+                ],
+                date,
+            ),
+        ];
+        assert!(input.contains(W3M_SECTION_END));
+        assert_eq!(expected, bookmarks_from_w3m_html(input, date));
+    }
+
+    #[test]
+    fn parse_lynx_bookmarks() {
+        let input = r#"<head>
+<META http-equiv="content-type" content="text/html;charset=utf-8">
+<title>Bookmark file</title>
+</head>
+     You can delete links by the 'R'emove command while viewing
+this bookmark file in Lynx.
+<p>
+<ol>
+<LI><a href="https://test.website/">Test</a>
+<LI><a href="https://other.website/">Other</a>
+</ol>
+"#;
+        let date = Timestamp::from_second(1740601105).unwrap();
+        let expected = vec![
+            link("Test", "https://test.website/", 0, date),
+            link("Other", "https://other.website/", 1, date),
+        ];
+        assert!(input.contains(LYNX_TITLE));
+        assert_eq!(expected, bookmarks_from_w3m_html(input, date));
+    }
+}
@@ -1,17 +1,67 @@
-use std::path::PathBuf;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use jiff::{civil::Date, tz::TimeZone, Timestamp};
+
+use crate::bookmarks::{
+    canonical::CanonicalOptions,
+    dedupe::DuplicateStrategy,
+    filter::FilterOptions,
+    model::{RootKind, RootOptions},
+    profiles::Browser,
+    BookmarkFormat, LoadOptions,
+};
 
 /// A simple tool for converting your browser bookmarks into Obsidian-flavored Markdown files with frontmatter properties
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 pub struct Cli {
-    /// Path to JSON bookmarks export (Firefox or Chrom(e|ium))
-    #[arg(short = 'b', long, value_name = "FILE")]
-    pub bookmarks: PathBuf,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Path to bookmarks export (a browser export or database, or a bookmark service export; see `--format`)
+    ///
+    /// Firefox `.jsonlz4` backups are decompressed automatically, and passing a `bookmarkbackups` directory picks its newest backup.
+    /// Can be given multiple times to merge several exports into one vault.
+    #[arg(
+        short = 'b',
+        long,
+        value_name = "FILE",
+        required_unless_present_any = ["browser", "bookmarks_list"]
+    )]
+    pub bookmarks: Vec<PathBuf>,
+    /// File listing bookmark exports to merge, one path per line (relative paths are relative to the list, `#` starts a comment)
+    #[arg(long, value_name = "FILE")]
+    pub bookmarks_list: Option<PathBuf>,
+    /// Imports from a browser profile (see the `profiles` command), merged with any `--bookmarks`
+    #[arg(long, value_enum, value_name = "BROWSER")]
+    pub browser: Option<Browser>,
+    /// Name (or directory name) of the browser profile to import, defaults to the browser's default profile
+    #[arg(long, value_name = "NAME", requires = "browser")]
+    pub profile: Option<String>,
+    /// Home directory to search for browser profiles, defaults to $HOME
+    #[arg(long, value_name = "DIRECTORY")]
+    pub home: Option<PathBuf>,
+    /// Format of the bookmarks file, detected from its contents when omitted
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub format: Option<BookmarkFormat>,
+    /// Skips bookmarks that can't be converted (reporting each one) instead of aborting the import
+    #[arg(long)]
+    pub lenient: bool,
+    /// Files bookmarks under a folder named after their first tag, for formats without folders (buku)
+    #[arg(long)]
+    pub first_tag_folders: bool,
     /// Path to vault/destination directory
-    #[arg(short = 'v', long, value_name = "DIRECTORY")]
-    pub vault: PathBuf,
+    #[arg(short = 'v', long, value_name = "DIRECTORY", required = true)]
+    pub vault: Option<PathBuf>,
+    /// Updates the notes of bookmarks imported before instead of skipping them
+    ///
+    /// Notes are found by `bookmark_id` (or URL) wherever they are in the vault. Only the `bookmark_*` properties are rewritten and new tags are added to the note's own; other properties and the note's text are kept.
+    #[arg(long)]
+    pub sync: bool,
     /// Optional namespace to prefix the Obsidian tag path
     ///
     /// e.g. a namespace of "bookmarks" applied to tag "school" would become `#bookmarks/school` in Obsidian
@@ -22,6 +72,65 @@ pub struct Cli {
     /// e.g. all bookmarks that share en.wikipedia.com as a common base-URL would be linked as decendants of a simple list of links in a note titled "en.wikipedia.com"
     #[arg(short = 'u', long)]
     pub baseurl_linking: bool,
+    /// Keeps URL fragments (`#...`) when working out canonical URLs
+    ///
+    /// Canonical URLs are used to spot duplicates, and are written as `bookmark_canonical_url` when they differ from the original.
+    #[arg(long)]
+    pub keep_fragments: bool,
+    /// Keeps tracking parameters (`utm_*`, `fbclid`, `gclid`, ...) when working out canonical URLs
+    #[arg(long)]
+    pub keep_tracking_params: bool,
+    /// Keeps the order of query parameters when working out canonical URLs, instead of sorting them
+    #[arg(long)]
+    pub keep_query_order: bool,
+    /// Keeps trailing slashes on paths when working out canonical URLs
+    #[arg(long)]
+    pub keep_trailing_slashes: bool,
+    /// Writes the canonical URL as `bookmark_url` instead of the URL as it was saved
+    #[arg(long)]
+    pub canonical_urls: bool,
+    /// Only imports bookmarks whose folder path and title match this glob, e.g. `toolbar/Dev/**` (can be given multiple times)
+    ///
+    /// Built-in roots are called toolbar, menu, other, mobile and reading-list. Folders left empty by any of the filters are dropped.
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Leaves out bookmarks whose folder path and title match this glob (can be given multiple times)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    /// Only imports bookmarks with this tag, or any of the tags if given multiple times
+    #[arg(long, value_name = "TAG")]
+    pub tag: Vec<String>,
+    /// Leaves out bookmarks with this tag (can be given multiple times)
+    #[arg(long, value_name = "TAG")]
+    pub exclude_tag: Vec<String>,
+    /// Only imports bookmarks on this domain or its subdomains, e.g. `github.com` or `*.example.*` (can be given multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub domain: Vec<String>,
+    /// Leaves out bookmarks on this domain or its subdomains (can be given multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude_domain: Vec<String>,
+    /// Only imports bookmarks added on or after this date (e.g. `2024-01-01`, or an RFC 3339 timestamp)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    pub added_after: Option<Timestamp>,
+    /// Only imports bookmarks added before this date
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    pub added_before: Option<Timestamp>,
+    /// Names the folder of a built-in root, e.g. `toolbar=Favorites` (can be given multiple times)
+    ///
+    /// Each browser's toolbar, menu, other and mobile folders (and Safari's Reading List) are mapped onto the same roots, named "Bookmarks Toolbar", "Bookmarks Menu", "Other Bookmarks", "Mobile Bookmarks" and "Reading List" by default.
+    #[arg(long, value_name = "ROOT=NAME", value_parser = parse_root_rename)]
+    pub rename_root: Vec<(RootKind, String)>,
+    /// Leaves a built-in root, and every bookmark in it, out of the vault
+    #[arg(long, value_enum, value_name = "ROOT")]
+    pub hide_root: Vec<RootKind>,
+    /// Puts the contents of a built-in root straight into the vault instead of a folder of its own
+    #[arg(long, value_enum, value_name = "ROOT")]
+    pub collapse_root: Vec<RootKind>,
+    /// What to do with a link saved more than once (compared by canonical URL)
+    ///
+    /// `merge` writes one note listing every folder the link was saved in, with the tags of every copy.
+    #[arg(long, value_enum, value_name = "STRATEGY", default_value_t)]
+    pub duplicates: DuplicateStrategy,
     /// Imports bookmarks as a flat list, disregarding folder hierarchy
     #[arg(short = 'f', long)]
     pub flatten: bool,
@@ -32,8 +141,100 @@ pub struct Cli {
     pub link_hierarchy: bool,
 }
 
+#[derive(Subcommand)]
+pub enum Command {
+    /// Lists the browser profiles found in the home directory and how many bookmarks each holds
+    Profiles,
+    /// Prints a report of links saved more than once, by canonical URL or by near-identical titles on the same domain
+    Dedupe,
+}
+
 impl Cli {
     pub fn start() -> Self {
         Self::parse()
     }
+
+    pub fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            format: self.format,
+            lenient: self.lenient,
+            first_tag_folders: self.first_tag_folders,
+            canonical: CanonicalOptions {
+                keep_fragment: self.keep_fragments,
+                keep_tracking: self.keep_tracking_params,
+                keep_query_order: self.keep_query_order,
+                keep_trailing_slash: self.keep_trailing_slashes,
+            },
+        }
+    }
+
+    pub fn filter_options(&self) -> FilterOptions {
+        FilterOptions {
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            tags: self.tag.clone(),
+            exclude_tags: self.exclude_tag.clone(),
+            domains: self.domain.clone(),
+            exclude_domains: self.exclude_domain.clone(),
+            added_after: self.added_after,
+            added_before: self.added_before,
+        }
+    }
+
+    pub fn root_options(&self) -> RootOptions {
+        RootOptions {
+            rename: self.rename_root.iter().cloned().collect(),
+            hide: self.hide_root.clone(),
+            collapse: self.collapse_root.clone(),
+        }
+    }
+
+    /// Every bookmark export to import: the `--bookmarks` paths followed by those in `--bookmarks-list`
+    pub fn bookmark_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = self.bookmarks.clone();
+        if let Some(list) = &self.bookmarks_list {
+            let contents = fs::read_to_string(list)
+                .with_context(|| format!("Failed to read {}", list.display()))?;
+            let base = list.parent().unwrap_or(Path::new(""));
+            paths.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| base.join(line)),
+            );
+        }
+        Ok(paths)
+    }
+
+    /// The directory browser profiles are searched for in
+    pub fn home(&self) -> PathBuf {
+        self.home
+            .clone()
+            .or_else(|| env::var_os("HOME").map(PathBuf::from))
+            .unwrap_or_default()
+    }
+}
+
+/// Parses a `--rename-root` value such as `toolbar=Favorites`
+fn parse_root_rename(value: &str) -> Result<(RootKind, String), String> {
+    let (root, name) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected ROOT=NAME, got \"{value}\""))?;
+    Ok((
+        RootKind::from_str(root.trim(), true)?,
+        name.trim().to_string(),
+    ))
+}
+
+/// Parses a `--added-*` value, either a timestamp or a date taken as midnight UTC
+fn parse_date(value: &str) -> Result<Timestamp, String> {
+    if let Ok(timestamp) = value.parse::<Timestamp>() {
+        return Ok(timestamp);
+    }
+    value
+        .parse::<Date>()
+        .and_then(|date| date.to_zoned(TimeZone::UTC))
+        .map(|date| date.timestamp())
+        .map_err(|err| err.to_string())
 }
@@ -1,14 +1,93 @@
-use cli::Cli;
+use anyhow::{bail, Result};
+use bookmarks::{
+    dedupe::{dedupe, find_duplicates},
+    filter::BookmarkFilter,
+    model::{merge_trees, Bookmarks},
+    profiles::{discover_profiles, find_profile, Browser},
+    LoadOptions,
+};
+use clap::ValueEnum;
+use cli::{Cli, Command};
 
 mod bookmarks;
 mod cli;
 mod obsidian;
 
-fn main() {
+fn main() -> Result<()> {
     let args = Cli::start();
+
+    if let Some(Command::Profiles) = args.command {
+        return list_profiles(&args);
+    }
+
+    let mut bookmarks = load_all(&args)?;
+    if let Some(Command::Dedupe) = args.command {
+        return report_duplicates(&bookmarks);
+    }
+    dedupe(&mut bookmarks, args.duplicates);
+    let vault = args.vault.as_ref().expect("clap requires --vault");
+    let summary = obsidian::export_to_vault(bookmarks, vault, &args)?;
+    println!("{summary}");
+
+    if summary.failed > 0 {
+        bail!("Failed to write {} of the bookmark notes", summary.failed);
+    }
+    Ok(())
+}
+
+/// Loads every source given on the command line, merged, filtered and arranged
+fn load_all(args: &Cli) -> Result<Bookmarks> {
+    let mut trees = vec![];
+    if let Some(browser) = args.browser {
+        let profile = find_profile(&args.home(), browser, args.profile.as_deref())?;
+        let mut tree = bookmarks::load_bookmarks(&profile.bookmarks, &args.load_options())?;
+        for source in &mut tree.sources {
+            source.browser = Some(browser);
+            source.profile = Some(profile.name.clone());
+        }
+        trees.push(tree);
+    }
+    for path in args.bookmark_paths()? {
+        trees.push(bookmarks::load_bookmarks(&path, &args.load_options())?);
+    }
+    // Labelled before merging, so merged notes can say where they came from
+    for tree in &mut trees {
+        tree.record_provenance();
+    }
+    let Some(mut bookmarks) = merge_trees(trees) else {
+        bail!("No bookmarks to import");
+    };
+    BookmarkFilter::new(&args.filter_options())?.apply(&mut bookmarks);
+    bookmarks.arrange_roots(&args.root_options());
+    Ok(bookmarks)
+}
+
+fn report_duplicates(bookmarks: &Bookmarks) -> Result<()> {
+    let groups = find_duplicates(bookmarks);
+    for group in &groups {
+        println!("{group}\n");
+    }
+    println!("Possible duplicates: {}", groups.len());
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn list_profiles(args: &Cli) -> Result<()> {
+    let profiles = discover_profiles(&args.home(), Browser::value_variants());
+    if profiles.is_empty() {
+        println!("No browser profiles found under {}", args.home().display());
+    }
+    for profile in profiles {
+        let count = match bookmarks::load_bookmarks(&profile.bookmarks, &LoadOptions::default()) {
+            Ok(bookmarks) => format!("{} bookmarks", bookmarks.link_count()),
+            Err(err) => format!("unreadable: {err:#}"),
+        };
+        println!(
+            "{}\t{}{}\t{}\t{count}",
+            profile.browser,
+            profile.name,
+            if profile.is_default { " (default)" } else { "" },
+            profile.path.display(),
+        );
+    }
+    Ok(())
 }
@@ -0,0 +1,751 @@
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use jiff::Timestamp;
+
+use crate::{
+    bookmarks::model::{BookmarkNode, Bookmarks},
+    cli::Cli,
+};
+
+mod state;
+mod sync;
+
+use state::VaultState;
+
+/// Tally of what happened to each bookmark during an export
+#[derive(Debug, Default, PartialEq)]
+pub struct ExportSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+impl fmt::Display for ExportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} notes created, {} updated, {} skipped, {} failed",
+            self.created, self.updated, self.skipped, self.failed
+        )
+    }
+}
+
+/// Whether a note was written, brought up to date, or left alone because it already existed (or
+/// was already up to date)
+#[derive(Debug, PartialEq)]
+enum WriteOutcome {
+    Created,
+    Updated,
+    Skipped,
+}
+
+fn build_tags(tags: Option<Vec<String>>, prefix: Option<String>) -> String {
+    match tags {
+        Some(tags) => tags
+            .into_iter()
+            .map(|tag| {
+                let tag = tag.to_lowercase();
+                match &prefix {
+                    Some(prefix) => format!("{prefix}/{tag}"),
+                    None => tag,
+                }
+            })
+            .fold("tags:".to_string(), |acc, tag| format!("{acc}\n- {tag}")),
+        None => "".to_string(),
+    }
+}
+
+fn fmt_timestamp(timestamp: Timestamp) -> String {
+    timestamp.strftime("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// Quotes a YAML scalar when leaving it bare would change how it is parsed
+fn yaml_string(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.trim() != value
+        || value.starts_with([
+            '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%',
+            '@', '`',
+        ])
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.ends_with(':')
+        || value.contains(['\n', '\r', '\t'])
+        || value.parse::<f64>().is_ok()
+        || matches!(
+            value.to_lowercase().as_str(),
+            "true" | "false" | "yes" | "no" | "on" | "off" | "null" | "~"
+        );
+    if !needs_quotes {
+        return value.to_string();
+    }
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{escaped}\"")
+}
+
+/// Replaces anything that isn't alphanumeric so the title is safe to use as a file or directory name
+fn sanitize_file_name(title: &str) -> String {
+    let name = title.replace(|c: char| !c.is_alphanumeric(), "_");
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name
+    }
+}
+
+/// Turns an annotation key such as `bookmarkProperties/loadInSidebar` into a snake_case property
+/// name (`bookmark_properties_load_in_sidebar`)
+fn property_name(key: &str) -> String {
+    let mut name = String::new();
+    let mut previous = '_';
+    for c in key.chars() {
+        let c = if c.is_alphanumeric() { c } else { '_' };
+        if c.is_uppercase() && previous.is_lowercase() {
+            name.push('_');
+        }
+        if c != '_' || previous != '_' {
+            name.extend(c.to_lowercase());
+        }
+        previous = c;
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+fn build_bookmark_file_contents(bookmark: &BookmarkNode, opts: Option<&Cli>) -> Result<String> {
+    let tag_namespace = match opts {
+        Some(Cli { tag_namespace, .. }) => tag_namespace.clone(),
+        None => None,
+    };
+    let canonical_urls = opts.is_some_and(|opts| opts.canonical_urls);
+    let BookmarkNode::Link {
+        title,
+        url,
+        add_date,
+        last_modified,
+        tags,
+        meta,
+    } = bookmark
+    else {
+        bail!("Expected BookmarkNode::Link, got a folder or separator");
+    };
+
+    let original_url = url;
+    // Whichever URL isn't shown as `bookmark_url` is still written when it's different
+    let (url, other_url) = match &meta.canonical_url {
+        Some(canonical) if canonical_urls => (canonical, Some(("bookmark_original_url", url))),
+        Some(canonical) => (url, Some(("bookmark_canonical_url", canonical))),
+        None => (url, None),
+    };
+    let mut properties = vec![
+        format!(
+            "bookmark_id: {}",
+            yaml_string(&meta.bookmark_id(original_url))
+        ),
+        format!("bookmark_title: {}", yaml_string(title)),
+        format!("bookmark_url: {}", yaml_string(url)),
+        format!("bookmark_add_date: {}", fmt_timestamp(*add_date)),
+        format!("bookmark_last_modified: {}", fmt_timestamp(*last_modified)),
+    ];
+    if let Some((key, other_url)) = other_url.filter(|(_, other_url)| *other_url != url) {
+        properties.push(format!("{key}: {}", yaml_string(other_url)));
+    }
+    if let Some(last_used) = meta.last_used {
+        properties.push(format!("bookmark_last_used: {}", fmt_timestamp(last_used)));
+    }
+    let optional = [
+        ("bookmark_guid", &meta.guid),
+        ("bookmark_description", &meta.description),
+        ("bookmark_keyword", &meta.keyword),
+        ("bookmark_post_data", &meta.post_data),
+        ("bookmark_icon", &meta.icon_uri),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            properties.push(format!("{key}: {}", yaml_string(value)));
+        }
+    }
+    // Everything else the source knew, e.g. Firefox annos, Chrome `meta_info` or Raindrop covers
+    for (key, value) in &meta.annotations {
+        let key = format!("bookmark_{}", property_name(key));
+        if !properties
+            .iter()
+            .any(|property| property.starts_with(&format!("{key}:")))
+        {
+            properties.push(format!("{key}: {}", yaml_string(value)));
+        }
+    }
+    if !meta.sources.is_empty() {
+        properties.push(
+            meta.sources
+                .iter()
+                .fold("bookmark_sources:".to_string(), |acc, source| {
+                    format!("{acc}\n- {}", yaml_string(source))
+                }),
+        );
+    }
+    if !meta.folders.is_empty() {
+        properties.push(
+            meta.folders
+                .iter()
+                .fold("bookmark_folders:".to_string(), |acc, folder| {
+                    format!("{acc}\n- {}", yaml_string(folder))
+                }),
+        );
+    }
+    let tags_list = build_tags(tags.clone(), tag_namespace);
+    if !tags_list.is_empty() {
+        properties.push(tags_list);
+    }
+
+    Ok(format!("---\n{}\n---", properties.join("\n")))
+}
+
+/// Writes a single bookmark note, leaving any existing note with the same name untouched
+fn write_bookmark_file(path: &Path, contents: &str) -> Result<WriteOutcome> {
+    let write = || -> Result<WriteOutcome> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = match fs::File::create_new(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                return Ok(WriteOutcome::Skipped)
+            }
+            Err(err) => return Err(err.into()),
+        };
+        file.write_all(contents.as_bytes())?;
+        file.write_all(b"\n")?;
+        Ok(WriteOutcome::Created)
+    };
+
+    write().with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Where a new note for a bookmark goes: `Title.md`, or `Title (2).md` and so on when the name is
+/// taken by another bookmark's note or one of the user's
+///
+/// A note already there for this bookmark (written before the manifest, say) is returned instead,
+/// flagged as existing.
+fn free_note_path(dir: &Path, title: &str, id: &str, url: &str) -> Result<(PathBuf, bool)> {
+    let name = sanitize_file_name(title);
+    let mut copy = 1;
+    loop {
+        let path = match copy {
+            1 => dir.join(format!("{name}.md")),
+            _ => dir.join(format!("{name} ({copy}).md")),
+        };
+        if !path.exists() {
+            return Ok((path, false));
+        }
+        if sync::note_is_for(&path, id, url)? {
+            return Ok((path, true));
+        }
+        copy += 1;
+    }
+}
+
+/// Writes one note per bookmark into the vault, mirroring the folder hierarchy unless `flatten` is set
+///
+/// Notes are tracked in the vault's [`VaultState`], so a bookmark whose note was moved or renamed
+/// isn't written again. With `sync`, bookmarks that already have a note update it instead, unless
+/// its frontmatter would come out the same as last time.
+pub fn export_to_vault(bookmarks: Bookmarks, vault: &Path, opts: &Cli) -> Result<ExportSummary> {
+    let mut summary = ExportSummary::default();
+
+    fs::create_dir_all(vault)?;
+    let mut state = VaultState::load(vault)?;
+    let mut index = None;
+    for visit in bookmarks.links() {
+        let BookmarkNode::Link {
+            title, url, meta, ..
+        } = visit.node
+        else {
+            continue;
+        };
+        let id = meta.bookmark_id(url);
+        // The manifest saves scanning the vault, unless the note has moved since the last run
+        let mut existing = state.note_path(vault, &id);
+        if existing.is_none() && (opts.sync || state.notes.contains_key(&id)) {
+            if index.is_none() {
+                index = Some(sync::VaultIndex::scan(vault)?);
+            }
+            existing = index
+                .as_ref()
+                .and_then(|index| index.find(&id, url))
+                .map(Path::to_path_buf);
+        }
+        let found = match existing {
+            Some(path) => Ok((path, true)),
+            None => {
+                let mut dir = vault.to_path_buf();
+                if !opts.flatten {
+                    dir.extend(visit.path.iter().map(|title| sanitize_file_name(title)));
+                }
+                free_note_path(&dir, title, &id, url)
+            }
+        };
+
+        let result = found.and_then(|(path, existing)| {
+            let contents = build_bookmark_file_contents(visit.node, Some(opts))?;
+            let outcome = match existing {
+                true if !opts.sync || state.is_unchanged(vault, &id, &path, &contents) => {
+                    WriteOutcome::Skipped
+                }
+                true => sync::update_note(&path, &contents)?,
+                false => write_bookmark_file(&path, &contents)?,
+            };
+            Ok((path, existing, outcome, contents))
+        });
+        match result {
+            Ok((path, existing, outcome, contents)) => {
+                let sources = meta.sources.clone();
+                if opts.sync && existing || outcome == WriteOutcome::Created {
+                    state.record(vault, id, &path, &contents, sources);
+                } else if existing {
+                    state.claim(vault, id, &path, sources);
+                }
+                match outcome {
+                    WriteOutcome::Created => summary.created += 1,
+                    WriteOutcome::Updated => summary.updated += 1,
+                    WriteOutcome::Skipped => summary.skipped += 1,
+                }
+            }
+            Err(err) => {
+                eprintln!("{err:#}");
+                summary.failed += 1;
+            }
+        }
+    }
+    state.save(vault)?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, fs, path::Path};
+
+    use clap::Parser;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        bookmarks::{
+            model::{BookmarkMeta, BookmarkNode, BookmarkSource, Bookmarks},
+            pinboard::bookmarks_from_pinboard_json,
+            raindrop::bookmarks_from_raindrop_csv,
+        },
+        cli::Cli,
+    };
+
+    use super::{
+        build_bookmark_file_contents, build_tags, export_to_vault, yaml_string, ExportSummary,
+        VaultState,
+    };
+
+    #[test]
+    fn build_tags_works() {
+        // Check basic functionality
+        let input = Some(vec!["test".to_string(), "test2".to_string()]);
+        let expected = r#"tags:
+- test
+- test2"#;
+        let result = build_tags(input.clone(), None);
+        assert_eq!(expected, result);
+        // Check prefix
+        let expected = r#"tags:
+- bookmarks/test
+- bookmarks/test2"#;
+        let result = build_tags(input, Some("bookmarks".to_string()));
+        assert_eq!(expected, result);
+        // Check None
+        let input = None;
+        let expected = "";
+        let result = build_tags(input, None);
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn yaml_string_works() {
+        assert_eq!("http://test.website/", yaml_string("http://test.website/"));
+        assert_eq!("\"Rust: The Book\"", yaml_string("Rust: The Book"));
+        assert_eq!("\"\\\"Quoted\\\" title\"", yaml_string("\"Quoted\" title"));
+        assert_eq!("\"2025\"", yaml_string("2025"));
+        assert_eq!("\"\"", yaml_string(""));
+    }
+
+    #[test]
+    fn build_bookmark_file_contents_works() {
+        let input = BookmarkNode::Link {
+            title: "Test".to_string(),
+            url: "http://test.website/".to_string(),
+            add_date: "2025-03-06T09:06:02Z".parse().unwrap(),
+            last_modified: "2025-03-06T09:06:02Z".parse().unwrap(),
+            tags: Some(vec!["test".to_string(), "test2".to_string()]),
+            meta: BookmarkMeta {
+                sources: vec!["firefox (default)".to_string(), "Bookmarks".to_string()],
+                ..Default::default()
+            },
+        };
+        let expected = r#"---
+bookmark_id: 8964dd582dd23c1f6b69e33e44f02ea5
+bookmark_title: Test
+bookmark_url: http://test.website/
+bookmark_add_date: 2025-03-06T09:06:02
+bookmark_last_modified: 2025-03-06T09:06:02
+bookmark_sources:
+- firefox (default)
+- Bookmarks
+tags:
+- test
+- test2
+---"#;
+        let result = build_bookmark_file_contents(&input, None).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn build_bookmark_file_contents_writes_both_urls() {
+        let input = BookmarkNode::Link {
+            title: "Test".to_string(),
+            url: "http://Test.website/?utm_source=feed".to_string(),
+            add_date: "2025-03-06T09:06:02Z".parse().unwrap(),
+            last_modified: "2025-03-06T09:06:02Z".parse().unwrap(),
+            tags: None,
+            meta: BookmarkMeta {
+                canonical_url: Some("http://test.website/".to_string()),
+                ..Default::default()
+            },
+        };
+        let result = build_bookmark_file_contents(&input, None).unwrap();
+        assert!(result.contains(
+            "bookmark_url: http://Test.website/?utm_source=feed\nbookmark_add_date: 2025-03-06T09:06:02\nbookmark_last_modified: 2025-03-06T09:06:02\nbookmark_canonical_url: http://test.website/\n"
+        ));
+
+        let opts = Cli::parse_from([
+            "bookmark2obsidian",
+            "--bookmarks",
+            "unused.json",
+            "--vault",
+            "unused",
+            "--canonical-urls",
+        ]);
+        let result = build_bookmark_file_contents(&input, Some(&opts)).unwrap();
+        assert!(result.contains("bookmark_url: http://test.website/\n"));
+        assert!(result.contains("bookmark_original_url: http://Test.website/?utm_source=feed\n"));
+    }
+
+    #[test]
+    fn build_bookmark_file_contents_writes_annotations() {
+        let input = BookmarkNode::Link {
+            title: "Test".to_string(),
+            url: "http://test.website/".to_string(),
+            add_date: "2025-03-06T09:06:02Z".parse().unwrap(),
+            last_modified: "2025-03-06T09:06:02Z".parse().unwrap(),
+            tags: None,
+            meta: BookmarkMeta {
+                description: Some("A description".to_string()),
+                annotations: BTreeMap::from([
+                    (
+                        "bookmarkProperties/loadInSidebar".to_string(),
+                        "1".to_string(),
+                    ),
+                    (
+                        "last_visited_desktop".to_string(),
+                        "13385703091000000".to_string(),
+                    ),
+                    ("description".to_string(), "Shadowed".to_string()),
+                ]),
+                ..Default::default()
+            },
+        };
+        let result = build_bookmark_file_contents(&input, None).unwrap();
+        assert!(result.contains(
+            "bookmark_description: A description\nbookmark_bookmark_properties_load_in_sidebar: \"1\"\nbookmark_last_visited_desktop: \"13385703091000000\"\n"
+        ));
+        assert!(!result.contains("Shadowed"));
+    }
+
+    #[test]
+    fn export_to_vault_works() {
+        let vault = tempfile::tempdir().unwrap();
+        let link = |title: &str| BookmarkNode::Link {
+            title: title.to_string(),
+            url: format!("http://{}.website/", title.to_lowercase()),
+            add_date: "2025-03-06T09:06:02Z".parse().unwrap(),
+            last_modified: "2025-03-06T09:06:02Z".parse().unwrap(),
+            tags: None,
+            meta: BookmarkMeta::default(),
+        };
+        let input = Bookmarks::new(
+            vec![
+                link("Top"),
+                BookmarkNode::Folder {
+                    title: "Test Folder".to_string(),
+                    meta: BookmarkMeta::default(),
+                    add_date: "2025-03-06T09:06:02Z".parse().unwrap(),
+                    last_modified: "2025-03-06T09:06:02Z".parse().unwrap(),
+                    children: vec![link("Nested"), link("Nested")],
+                },
+            ],
+            BookmarkSource::default(),
+        );
+        let opts = Cli::parse_from([
+            "bookmark2obsidian",
+            "--bookmarks",
+            "unused.json",
+            "--vault",
+            vault.path().to_str().unwrap(),
+        ]);
+
+        let result = export_to_vault(input.clone(), vault.path(), &opts).unwrap();
+        assert_eq!(
+            ExportSummary {
+                created: 2,
+                updated: 0,
+                skipped: 1,
+                failed: 0
+            },
+            result
+        );
+        assert!(vault.path().join("Top.md").is_file());
+        let nested = fs::read_to_string(vault.path().join("Test_Folder/Nested.md")).unwrap();
+        assert!(nested.contains("\nbookmark_title: Nested\n"));
+
+        // A second run must not touch the notes written by the first
+        let result = export_to_vault(input, vault.path(), &opts).unwrap();
+        assert_eq!(
+            ExportSummary {
+                created: 0,
+                updated: 0,
+                skipped: 3,
+                failed: 0
+            },
+            result
+        );
+    }
+
+    #[test]
+    fn export_to_vault_syncs_notes_without_ids_whatever_the_canonical_options() {
+        let vault = tempfile::tempdir().unwrap();
+        fs::write(
+            vault.path().join("Old.md"),
+            "---\nbookmark_url: http://test.website/#top\n---\nMy notes\n",
+        )
+        .unwrap();
+        let input = Bookmarks::new(
+            vec![BookmarkNode::Link {
+                title: "Test".to_string(),
+                url: "http://test.website/#top".to_string(),
+                add_date: "2025-03-06T09:06:02Z".parse().unwrap(),
+                last_modified: "2025-03-06T09:06:02Z".parse().unwrap(),
+                tags: None,
+                meta: BookmarkMeta {
+                    canonical_url: Some("http://test.website/#top".to_string()),
+                    ..Default::default()
+                },
+            }],
+            BookmarkSource::default(),
+        );
+        let opts = Cli::parse_from([
+            "bookmark2obsidian",
+            "--bookmarks",
+            "unused.json",
+            "--vault",
+            vault.path().to_str().unwrap(),
+            "--sync",
+            "--keep-fragments",
+        ]);
+
+        let result = export_to_vault(input, vault.path(), &opts).unwrap();
+        assert_eq!((0, 1), (result.created, result.updated));
+        let note = fs::read_to_string(vault.path().join("Old.md")).unwrap();
+        assert!(note.contains("\nbookmark_title: Test\n"));
+        assert!(note.ends_with("---\nMy notes\n"));
+    }
+
+    #[test]
+    fn export_to_vault_gives_clashing_titles_their_own_notes() {
+        let vault = tempfile::tempdir().unwrap();
+        let link = |url: &str| BookmarkNode::Link {
+            title: "Test".to_string(),
+            url: url.to_string(),
+            add_date: "2025-03-06T09:06:02Z".parse().unwrap(),
+            last_modified: "2025-03-06T09:06:02Z".parse().unwrap(),
+            tags: None,
+            meta: BookmarkMeta::default(),
+        };
+        let input = Bookmarks::new(
+            vec![link("http://one.website/"), link("http://two.website/")],
+            BookmarkSource::default(),
+        );
+        let opts = |sync: bool| {
+            let vault = vault.path().to_str().unwrap();
+            let mut args = vec!["bookmark2obsidian", "-b", "unused.json", "-v", vault];
+            if sync {
+                args.push("--sync");
+            }
+            Cli::parse_from(args)
+        };
+        // One of the user's own notes already has the name
+        fs::write(vault.path().join("Test.md"), "Not a bookmark\n").unwrap();
+
+        let result = export_to_vault(input.clone(), vault.path(), &opts(false)).unwrap();
+        assert_eq!(2, result.created);
+        let note = |name: &str| fs::read_to_string(vault.path().join(name)).unwrap();
+        assert_eq!("Not a bookmark\n", note("Test.md"));
+        assert!(note("Test (2).md").contains("\nbookmark_url: http://one.website/\n"));
+        assert!(note("Test (3).md").contains("\nbookmark_url: http://two.website/\n"));
+        let state = VaultState::load(vault.path()).unwrap();
+        let mut paths = state
+            .notes
+            .values()
+            .map(|note| note.path.to_str().unwrap())
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(vec!["Test (2).md", "Test (3).md"], paths);
+
+        for sync in [false, true] {
+            let result = export_to_vault(input.clone(), vault.path(), &opts(sync)).unwrap();
+            assert_eq!((0, 2), (result.created, result.skipped));
+        }
+        assert!(!vault.path().join("Test (4).md").exists());
+    }
+
+    #[test]
+    fn export_to_vault_records_notes_it_already_wrote() {
+        let vault = tempfile::tempdir().unwrap();
+        let input = Bookmarks::new(
+            vec![BookmarkNode::Link {
+                title: "Test".to_string(),
+                url: "http://test.website/".to_string(),
+                add_date: "2025-03-06T09:06:02Z".parse().unwrap(),
+                last_modified: "2025-03-06T09:06:02Z".parse().unwrap(),
+                tags: None,
+                meta: BookmarkMeta {
+                    sources: vec!["bookmarks.html".to_string()],
+                    ..Default::default()
+                },
+            }],
+            BookmarkSource::default(),
+        );
+        let opts = Cli::parse_from([
+            "bookmark2obsidian",
+            "--bookmarks",
+            "unused.json",
+            "--vault",
+            vault.path().to_str().unwrap(),
+        ]);
+        export_to_vault(input.clone(), vault.path(), &opts).unwrap();
+        fs::remove_file(vault.path().join(".bookmark2obsidian/state.json")).unwrap();
+
+        let result = export_to_vault(input, vault.path(), &opts).unwrap();
+        assert_eq!((0, 1), (result.created, result.skipped));
+        let state = VaultState::load(vault.path()).unwrap();
+        let notes = state
+            .notes
+            .values()
+            .map(|note| (note.path.to_str().unwrap(), note.sources.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![("Test.md", vec!["bookmarks.html".to_string()])], notes);
+    }
+
+    #[test]
+    fn export_to_vault_keeps_importer_fields() {
+        let vault = tempfile::tempdir().unwrap();
+        let raindrop = bookmarks_from_raindrop_csv(
+            "id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite\n\
+            1,Raindrop,My note,,https://raindrop.website/,,,2025-02-26T20:18:25Z,https://raindrop.website/cover.png,\"Line one\nLine two\",true\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let pinboard = bookmarks_from_pinboard_json(
+            r#"[{"href": "https://pinboard.website/", "description": "Pinboard", "time": "2025-02-26T20:18:25Z", "shared": "no", "toread": "yes"}]"#,
+        )
+        .unwrap();
+        let input = Bookmarks::new([raindrop, pinboard].concat(), BookmarkSource::default());
+        let opts = Cli::parse_from([
+            "bookmark2obsidian",
+            "--bookmarks",
+            "unused.json",
+            "--vault",
+            vault.path().to_str().unwrap(),
+        ]);
+        export_to_vault(input, vault.path(), &opts).unwrap();
+
+        let raindrop = fs::read_to_string(vault.path().join("Raindrop.md")).unwrap();
+        for property in [
+            "bookmark_cover: https://raindrop.website/cover.png\n",
+            "bookmark_favorite: \"true\"\n",
+            "bookmark_highlights: \"Line one\\nLine two\"\n",
+            "bookmark_note: My note\n",
+        ] {
+            assert!(
+                raindrop.contains(property),
+                "{property} missing from {raindrop}"
+            );
+        }
+        let pinboard = fs::read_to_string(vault.path().join("Pinboard.md")).unwrap();
+        assert!(pinboard.contains("bookmark_shared: \"no\"\nbookmark_toread: \"yes\"\n"));
+    }
+
+    #[test]
+    fn export_to_vault_syncs_existing_notes() {
+        let vault = tempfile::tempdir().unwrap();
+        let link = |title: &str, tags: &[&str]| BookmarkNode::Link {
+            title: title.to_string(),
+            url: "http://test.website/".to_string(),
+            add_date: "2025-03-06T09:06:02Z".parse().unwrap(),
+            last_modified: "2025-03-06T09:06:02Z".parse().unwrap(),
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            meta: BookmarkMeta::default(),
+        };
+        let opts = |sync: bool| {
+            let vault = vault.path().to_str().unwrap();
+            let mut args = vec!["bookmark2obsidian", "-b", "unused.json", "-v", vault];
+            if sync {
+                args.push("--sync");
+            }
+            Cli::parse_from(args)
+        };
+        let first = Bookmarks::new(vec![link("Test", &["a"])], BookmarkSource::default());
+        export_to_vault(first, vault.path(), &opts(false)).unwrap();
+
+        // The user moves the note and annotates it, then renames the bookmark
+        fs::create_dir(vault.path().join("Reading")).unwrap();
+        let moved = vault.path().join("Reading/Test.md");
+        let note = fs::read_to_string(vault.path().join("Test.md")).unwrap();
+        let note = note.replace("tags:", "status: read\ntags:") + "My notes\n";
+        fs::write(&moved, note).unwrap();
+        fs::remove_file(vault.path().join("Test.md")).unwrap();
+        let renamed = Bookmarks::new(vec![link("Renamed", &["b"])], BookmarkSource::default());
+
+        // The manifest knows the note, so even without sync it isn't written again
+        let result = export_to_vault(renamed.clone(), vault.path(), &opts(false)).unwrap();
+        assert_eq!((0, 1), (result.created, result.skipped));
+        assert!(!vault.path().join("Renamed.md").exists());
+
+        let result = export_to_vault(renamed.clone(), vault.path(), &opts(true)).unwrap();
+        assert_eq!(1, result.updated);
+        assert!(!vault.path().join("Renamed.md").exists());
+        let note = fs::read_to_string(&moved).unwrap();
+        assert!(note.contains("\nbookmark_title: Renamed\n"));
+        assert!(note.contains("\nstatus: read\ntags:\n- a\n- b\n---\nMy notes\n"));
+
+        let result = export_to_vault(renamed, vault.path(), &opts(true)).unwrap();
+        assert_eq!((0, 1), (result.updated, result.skipped));
+        let state = VaultState::load(vault.path()).unwrap();
+        let note = state.notes.values().next().unwrap();
+        assert_eq!(Path::new("Reading/Test.md"), note.path);
+    }
+}
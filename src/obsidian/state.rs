@@ -0,0 +1,153 @@
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// Where the manifest lives, relative to the vault
+const STATE_PATH: &str = ".bookmark2obsidian/state.json";
+
+/// The manifest of every note the tool has written into a vault, keyed by `bookmark_id`
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct VaultState {
+    pub notes: BTreeMap<String, NoteState>,
+}
+
+/// What the tool last wrote for one bookmark
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteState {
+    /// Relative to the vault
+    pub path: PathBuf,
+    /// Hash of the generated frontmatter, so unchanged bookmarks can be skipped
+    pub hash: String,
+    /// The browser profiles or files the bookmark was imported from
+    pub sources: Vec<String>,
+    pub last_sync: Timestamp,
+}
+
+impl VaultState {
+    /// Reads the vault's manifest, or starts an empty one if there isn't one yet
+    pub fn load(vault: &Path) -> Result<Self> {
+        let path = vault.join(STATE_PATH);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to read {}", path.display()))
+            }
+        };
+        serde_json::from_str(&contents).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    pub fn save(&self, vault: &Path) -> Result<()> {
+        let path = vault.join(STATE_PATH);
+        let write = || -> Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, serde_json::to_string_pretty(self)? + "\n")?;
+            Ok(())
+        };
+        write().with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Where the bookmark's note was last written, if it's still there
+    pub fn note_path(&self, vault: &Path, id: &str) -> Option<PathBuf> {
+        let path = vault.join(&self.notes.get(id)?.path);
+        path.is_file().then_some(path)
+    }
+
+    /// Whether the note at `path` already has exactly this frontmatter from the last run
+    pub fn is_unchanged(&self, vault: &Path, id: &str, path: &Path, contents: &str) -> bool {
+        self.notes
+            .get(id)
+            .is_some_and(|note| vault.join(&note.path) == path && note.hash == hash(contents))
+    }
+
+    /// Records that the bookmark's note is at `path`, without claiming its frontmatter is up to
+    /// date
+    pub fn claim(&mut self, vault: &Path, id: String, path: &Path, sources: Vec<String>) {
+        let path = path.strip_prefix(vault).unwrap_or(path).to_path_buf();
+        match self.notes.entry(id) {
+            Entry::Occupied(mut entry) => {
+                let note = entry.get_mut();
+                note.path = path;
+                note.sources = sources;
+            }
+            // No hash matches an empty one, so the note is rewritten on the next sync
+            Entry::Vacant(entry) => {
+                entry.insert(NoteState {
+                    path,
+                    hash: String::new(),
+                    sources,
+                    last_sync: Timestamp::now(),
+                });
+            }
+        }
+    }
+
+    /// Records that the bookmark's note at `path` now has `contents` as its frontmatter
+    pub fn record(
+        &mut self,
+        vault: &Path,
+        id: String,
+        path: &Path,
+        contents: &str,
+        sources: Vec<String>,
+    ) {
+        let path = path.strip_prefix(vault).unwrap_or(path).to_path_buf();
+        let note = NoteState {
+            path,
+            hash: hash(contents),
+            sources,
+            last_sync: Timestamp::now(),
+        };
+        self.notes.insert(id, note);
+    }
+}
+
+fn hash(contents: &str) -> String {
+    format!("{:x}", md5::compute(contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn vault_state_round_trips() {
+        let vault = tempfile::tempdir().unwrap();
+        assert_eq!(
+            VaultState::default(),
+            VaultState::load(vault.path()).unwrap()
+        );
+
+        let note = vault.path().join("Dev/Test.md");
+        fs::create_dir(vault.path().join("Dev")).unwrap();
+        fs::write(&note, "---\nbookmark_id: abc\n---\n").unwrap();
+        let mut state = VaultState::default();
+        let sources = vec!["bookmarks.html".to_string()];
+        state.record(vault.path(), "abc".to_string(), &note, "---", sources);
+        state.save(vault.path()).unwrap();
+
+        let state = VaultState::load(vault.path()).unwrap();
+        assert_eq!(Path::new("Dev/Test.md"), state.notes["abc"].path);
+        assert_eq!(Some(note.clone()), state.note_path(vault.path(), "abc"));
+        assert!(state.is_unchanged(vault.path(), "abc", &note, "---"));
+        assert!(!state.is_unchanged(vault.path(), "abc", &note, "---\n"));
+        assert!(!state.is_unchanged(vault.path(), "def", &note, "---"));
+
+        fs::remove_file(&note).unwrap();
+        assert_eq!(None, state.note_path(vault.path(), "abc"));
+
+        let mut state = VaultState::default();
+        state.claim(vault.path(), "abc".to_string(), &note, vec![]);
+        assert_eq!(Path::new("Dev/Test.md"), state.notes["abc"].path);
+        assert!(!state.is_unchanged(vault.path(), "abc", &note, ""));
+    }
+}
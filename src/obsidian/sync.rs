@@ -0,0 +1,395 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::bookmarks::canonical::stable_url;
+
+use super::{yaml_string, WriteOutcome};
+
+/// Prefix of the frontmatter keys the tool owns; everything else in a note belongs to the user
+const OWNED_PREFIX: &str = "bookmark_";
+
+/// The bookmark notes already in a vault, by `bookmark_id` and by [`stable_url`]
+#[derive(Debug, Default)]
+pub struct VaultIndex {
+    by_id: HashMap<String, PathBuf>,
+    by_url: HashMap<String, PathBuf>,
+}
+
+impl VaultIndex {
+    /// Reads the frontmatter of every note in the vault, skipping hidden directories such as
+    /// `.obsidian`
+    pub fn scan(vault: &Path) -> Result<Self> {
+        let mut index = Self::default();
+        index.scan_dir(vault)?;
+        Ok(index)
+    }
+
+    fn scan_dir(&mut self, dir: &Path) -> Result<()> {
+        let entries =
+            fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+            {
+                continue;
+            }
+            if path.is_dir() {
+                self.scan_dir(&path)?;
+            } else if path.extension().is_some_and(|ext| ext == "md") {
+                let contents = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                self.add(&contents, path);
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, contents: &str, path: PathBuf) {
+        let Some((frontmatter, _)) = split_note(contents) else {
+            return;
+        };
+        let properties = properties(frontmatter);
+        let value = |key: &str| {
+            properties
+                .iter()
+                .find(|property| property.key == key)
+                .map(Property::value)
+        };
+        // With `--canonical-urls`, the URL as saved is the original one
+        for key in ["bookmark_url", "bookmark_original_url"] {
+            if let Some(url) = value(key) {
+                self.by_url
+                    .entry(stable_url(&url))
+                    .or_insert_with(|| path.clone());
+            }
+        }
+        if let Some(id) = value("bookmark_id") {
+            self.by_id.entry(id).or_insert(path);
+        }
+    }
+
+    /// The note for a bookmark, found by its ID or else by its URL (for notes written before IDs
+    /// were)
+    pub fn find(&self, id: &str, url: &str) -> Option<&Path> {
+        self.by_id
+            .get(id)
+            .or_else(|| self.by_url.get(&stable_url(url)))
+            .map(PathBuf::as_path)
+    }
+}
+
+/// Whether the note at `path` is the one for a bookmark, going by its ID or URL
+pub fn note_is_for(path: &Path, id: &str, url: &str) -> Result<bool> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut index = VaultIndex::default();
+    index.add(&contents, path.to_path_buf());
+    Ok(index.find(id, url).is_some())
+}
+
+/// Rewrites the tool's frontmatter in an existing note, leaving the user's properties and the
+/// body alone
+pub fn update_note(path: &Path, generated: &str) -> Result<WriteOutcome> {
+    let update = || -> Result<WriteOutcome> {
+        let existing = fs::read_to_string(path)?;
+        let updated = merge_note(&existing, generated);
+        if updated == existing {
+            return Ok(WriteOutcome::Skipped);
+        }
+        fs::write(path, updated)?;
+        Ok(WriteOutcome::Updated)
+    };
+    update().with_context(|| format!("Failed to update {}", path.display()))
+}
+
+/// Replaces the `bookmark_*` properties of `existing` with those of `generated` and adds any new
+/// tags to the user's own
+///
+/// The tool's properties go where the first of them was, or at the top for a note that had none.
+/// A note without frontmatter gets the generated frontmatter above its contents.
+fn merge_note(existing: &str, generated: &str) -> String {
+    let Some((generated, _)) = split_note(generated) else {
+        return existing.to_string();
+    };
+    let generated = properties(generated);
+    let Some((frontmatter, body)) = split_note(existing) else {
+        return format!("---\n{}\n---\n{existing}", join(&generated));
+    };
+
+    let (owned, generated_tags) = generated
+        .into_iter()
+        .partition::<Vec<_>, _>(|property| property.key.starts_with(OWNED_PREFIX));
+    let generated_tags = generated_tags
+        .iter()
+        .find(|property| property.key == "tags")
+        .map(Property::items)
+        .unwrap_or_default();
+
+    let mut merged = vec![];
+    let mut owned = Some(owned);
+    let mut tags_seen = false;
+    for property in properties(frontmatter) {
+        if property.key.starts_with(OWNED_PREFIX) {
+            merged.extend(owned.take().into_iter().flatten());
+        } else if property.key == "tags" {
+            tags_seen = true;
+            merged.push(merge_tags(property, &generated_tags));
+        } else {
+            merged.push(property);
+        }
+    }
+    if let Some(owned) = owned {
+        merged.splice(0..0, owned);
+    }
+    if !tags_seen && !generated_tags.is_empty() {
+        merged.push(tags_property(&generated_tags));
+    }
+    format!("---\n{}\n---{body}", join(&merged))
+}
+
+/// The user's tags with any new ones appended, or the property untouched if nothing is new
+fn merge_tags(property: Property, generated: &[String]) -> Property {
+    let mut tags = property.items();
+    let new = generated
+        .iter()
+        .filter(|tag| {
+            !tags
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(tag))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if new.is_empty() {
+        return property;
+    }
+    tags.extend(new);
+    tags_property(&tags)
+}
+
+fn tags_property(tags: &[String]) -> Property {
+    Property {
+        key: "tags".to_string(),
+        text: tags.iter().fold("tags:".to_string(), |acc, tag| {
+            format!("{acc}\n- {}", yaml_string(tag))
+        }),
+    }
+}
+
+/// A top-level frontmatter property along with the lines of any list or block under it
+#[derive(Debug, Clone, PartialEq)]
+struct Property {
+    key: String,
+    text: String,
+}
+
+impl Property {
+    /// The property's value as a single (unquoted) scalar
+    fn value(&self) -> String {
+        let (_, value) = self.text.split_once(':').unwrap_or_default();
+        unquote(value.lines().next().unwrap_or_default().trim())
+    }
+
+    /// The property's value as a list, written either as a block or inline
+    fn items(&self) -> Vec<String> {
+        let (_, value) = self.text.split_once(':').unwrap_or_default();
+        let mut lines = value.lines();
+        let first = lines.next().unwrap_or_default().trim();
+        let inline = match first.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Some(list) => list.split(',').map(str::trim).collect(),
+            None => vec![first],
+        };
+        let block = lines.filter_map(|line| line.trim().strip_prefix('-').map(str::trim));
+        inline
+            .into_iter()
+            .chain(block)
+            .filter(|item| !item.is_empty())
+            .map(unquote)
+            .collect()
+    }
+}
+
+/// Splits a note into its frontmatter (without the `---` lines) and everything after the closing
+/// `---`
+fn split_note(contents: &str) -> Option<(&str, &str)> {
+    let rest = contents
+        .strip_prefix("---\n")
+        .or_else(|| contents.strip_prefix("---\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let frontmatter = rest[..offset].trim_end_matches(['\r', '\n']);
+            return Some((frontmatter, &rest[offset + 3..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Splits frontmatter into its top-level properties; comments and blank lines stay with the
+/// property above them
+fn properties(frontmatter: &str) -> Vec<Property> {
+    let mut properties: Vec<Property> = vec![];
+    for line in frontmatter.lines() {
+        let key = line
+            .split_once(':')
+            .map(|(key, _)| key)
+            .filter(|key| !key.is_empty() && !key.starts_with([' ', '\t', '-', '#']));
+        match (key, properties.last_mut()) {
+            (Some(key), _) => properties.push(Property {
+                key: key.to_string(),
+                text: line.to_string(),
+            }),
+            (None, Some(property)) => {
+                property.text.push('\n');
+                property.text.push_str(line);
+            }
+            (None, None) => properties.push(Property {
+                key: String::new(),
+                text: line.to_string(),
+            }),
+        }
+    }
+    properties
+}
+
+fn join(properties: &[Property]) -> String {
+    properties
+        .iter()
+        .map(|property| property.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Undoes the quoting [`yaml_string`] (or a person) may have applied to a scalar
+fn unquote(value: &str) -> String {
+    if let Some(value) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let mut unescaped = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some('t') => unescaped.push('\t'),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('\\'),
+            }
+        }
+        return unescaped;
+    }
+    if let Some(value) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return value.replace("''", "'");
+    }
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const GENERATED: &str = r#"---
+bookmark_id: mAQ-wHKmGwfH
+bookmark_title: "Rust: The Book"
+bookmark_url: https://doc.rust-lang.org/book/
+bookmark_add_date: 2025-03-06T09:06:02
+tags:
+- rust
+- books
+---"#;
+
+    #[test]
+    fn merge_note_keeps_user_edits() {
+        let existing = r#"---
+rating: 5
+bookmark_id: mAQ-wHKmGwfH
+bookmark_title: The Book
+bookmark_url: https://doc.rust-lang.org/book/
+bookmark_keyword: book
+tags: [Rust, to-read]
+aliases:
+- TRPL
+---
+# My notes
+
+Chapter 4 is the one about ownership.
+"#;
+        let expected = r#"---
+rating: 5
+bookmark_id: mAQ-wHKmGwfH
+bookmark_title: "Rust: The Book"
+bookmark_url: https://doc.rust-lang.org/book/
+bookmark_add_date: 2025-03-06T09:06:02
+tags:
+- Rust
+- to-read
+- books
+aliases:
+- TRPL
+---
+# My notes
+
+Chapter 4 is the one about ownership.
+"#;
+        assert_eq!(expected, merge_note(existing, GENERATED));
+        // Nothing changes the second time round
+        assert_eq!(expected, merge_note(expected, GENERATED));
+    }
+
+    #[test]
+    fn merge_note_adds_missing_frontmatter() {
+        let existing = "---\nstatus: read\n---\nNotes\n";
+        let expected = r#"---
+bookmark_id: mAQ-wHKmGwfH
+bookmark_title: "Rust: The Book"
+bookmark_url: https://doc.rust-lang.org/book/
+bookmark_add_date: 2025-03-06T09:06:02
+status: read
+tags:
+- rust
+- books
+---
+Notes
+"#;
+        assert_eq!(expected, merge_note(existing, GENERATED));
+        assert_eq!(
+            format!("{GENERATED}\nJust text\n"),
+            merge_note("Just text\n", GENERATED)
+        );
+    }
+
+    #[test]
+    fn vault_index_finds_notes_by_id_and_url() {
+        let vault = tempfile::tempdir().unwrap();
+        fs::create_dir_all(vault.path().join("Moved/Here")).unwrap();
+        fs::create_dir_all(vault.path().join(".obsidian")).unwrap();
+        fs::write(vault.path().join("Moved/Here/Book.md"), GENERATED).unwrap();
+        fs::write(
+            vault.path().join("Old.md"),
+            "---\nbookmark_url: \"HTTPS://Test.Website/#top\"\n---\n",
+        )
+        .unwrap();
+        fs::write(vault.path().join(".obsidian/Hidden.md"), GENERATED).unwrap();
+
+        let index = VaultIndex::scan(vault.path()).unwrap();
+        assert_eq!(
+            Some(vault.path().join("Moved/Here/Book.md").as_path()),
+            index.find("mAQ-wHKmGwfH", "https://elsewhere.website/")
+        );
+        assert_eq!(
+            Some(vault.path().join("Old.md").as_path()),
+            index.find("unknown", "https://test.website/")
+        );
+        assert_eq!(None, index.find("unknown", "https://elsewhere.website/"));
+    }
+}